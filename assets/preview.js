@@ -0,0 +1,41 @@
+// Reloads the note when it changes and scrolls to the line under the cursor of the editor.
+(function () {
+  const script = document.currentScript;
+  const source = new EventSource(script.dataset.events);
+
+  function content(doc) {
+    return doc.querySelector('main.content');
+  }
+
+  function sourceLines() {
+    const element = document.getElementById('source-lines');
+    return element ? JSON.parse(element.textContent) : [];
+  }
+
+  source.addEventListener('change', async function () {
+    const response = await fetch(window.location.href, { cache: 'no-store' });
+    if (!response.ok) return;
+
+    const doc = new DOMParser().parseFromString(await response.text(), 'text/html');
+    content(document).innerHTML = content(doc).innerHTML;
+    const lines = doc.getElementById('source-lines');
+    if (lines) document.getElementById('source-lines').textContent = lines.textContent;
+  });
+
+  source.addEventListener('cursor', function (event) {
+    const line = JSON.parse(event.data).line;
+    const lines = sourceLines();
+    const blocks = Array.from(content(document).children);
+
+    if (lines.length === blocks.length && lines.length > 0) {
+      let index = 0;
+      while (index + 1 < lines.length && lines[index + 1] <= line) index++;
+      blocks[index].scrollIntoView({ behavior: 'smooth', block: 'center' });
+      return;
+    }
+
+    const total = lines.length > 0 ? lines[lines.length - 1] + 1 : 1;
+    const height = document.documentElement.scrollHeight - window.innerHeight;
+    window.scrollTo({ top: height * Math.min(line / total, 1), behavior: 'smooth' });
+  });
+})();
@@ -126,9 +126,29 @@ function! noteutil#preview() abort
                     \ "stoponexit": "term"})
     endif
 
-    " TODO make the port configurable
-    let url = "http://localhost:10428/pages/" . expand("%")
+    let base_url = get(g:, 'noteutil_http_url', 'http://localhost:10428')
+    let url = base_url . "/pages/" . expand("%")
     call s:open_url(url)
+
+    augroup noteutil_preview
+        autocmd! * <buffer>
+        autocmd CursorMoved,CursorMovedI <buffer> call s:preview_sync_cursor()
+    augroup END
+endfunction
+
+" Scroll the preview to the cursor, debounced to avoid a request per keystroke
+function! s:preview_sync_cursor() abort
+    if exists('s:timer_preview_sync')
+        call timer_stop(s:timer_preview_sync)
+    endif
+    let s:timer_preview_sync = timer_start(200, {-> s:preview_post_cursor(
+                \ expand('%'), line('.') - 1)})
+endfunction
+
+function! s:preview_post_cursor(path, line) abort
+    let base_url = get(g:, 'noteutil_http_url', 'http://localhost:10428')
+    let url = base_url . '/_cursor/' . a:path . '?line=' . a:line
+    call job_start(['curl', '--silent', '--request', 'POST', url])
 endfunction
 
 function! s:open_url(url) abort
@@ -0,0 +1,585 @@
+// JSON API over the notes of the root directory. Notes are addressed by their path relative to
+// the root directory and carry an ETag, the SHA-256 of their content. Requests modifying a note
+// may send it in `If-Match` to make sure they don't override changes they haven't seen.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+
+/// Directory under the root directory where deleted notes are moved.
+pub const TRASH_DIR: &str = ".trash";
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct Note {
+    /// Path relative to the root directory, separated by slashes.
+    pub path: String,
+    pub title: String,
+    pub front_matter: crate::FrontMatter,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<crate::Link>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct Backlink {
+    pub path: String,
+    pub title: String,
+    pub link: crate::Link,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct Journal {
+    pub path: String,
+    pub exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<Note>,
+    /// Why the journal couldn't be read although it exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct Content {
+    pub content: String,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Filters {
+    /// Tags separated by commas.
+    tag: String,
+    dir: String,
+}
+
+impl Note {
+    fn summary(root_dir: &Path, note: &crate::Note) -> Self {
+        Self {
+            path: crate::http::relative_path(root_dir, &note.path),
+            title: note.title.clone(),
+            front_matter: note.front_matter.clone(),
+            tags: note.tags(),
+            links: None,
+            content: None,
+        }
+    }
+}
+
+pub async fn list_notes(
+    State(state): State<crate::http::ServerState>,
+    Query(filters): Query<Filters>,
+) -> Response {
+    let root_dir = state.config.root_dir.clone();
+    let mut notes: Vec<Note> = crate::http::with_visible_notes(&state, move |notes| {
+        let tags: Vec<&str> = filters
+            .tag
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        let dir = filters.dir.trim_matches('/');
+        notes
+            .iter()
+            .map(|note| Note::summary(&root_dir, note))
+            .filter(|note| dir.is_empty() || note.path.starts_with(&format!("{}/", dir)))
+            .filter(|note| tags.iter().all(|tag| note.tags.iter().any(|t| t == tag)))
+            .collect()
+    })
+    .await;
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Json(notes).into_response()
+}
+
+pub async fn list_tags(State(state): State<crate::http::ServerState>) -> Response {
+    let tags = crate::http::with_visible_notes(&state, |notes| {
+        let mut tags: BTreeMap<String, usize> = BTreeMap::new();
+        for note in notes {
+            for tag in note.tags() {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+        tags
+    })
+    .await;
+
+    Json(tags).into_response()
+}
+
+/// Serves a note, or the notes linking to it when the path ends with `/backlinks`.
+pub async fn get_note(
+    State(state): State<crate::http::ServerState>,
+    UrlPath(path): UrlPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(path) = path.strip_suffix("/backlinks") {
+        return backlinks(&state, path).await;
+    }
+
+    let Some(filepath) = note_path(&state.config, &path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let content = match std::fs::read_to_string(&filepath) {
+        Ok(content) => content,
+        Err(err) => return io_error(err, &filepath),
+    };
+
+    let etag = etag(content.as_bytes());
+    if header_matches(&headers, header::IF_NONE_MATCH, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    match note(&state.config.root_dir, &path, content) {
+        Ok(note) => (StatusCode::OK, [(header::ETAG, etag)], Json(note)).into_response(),
+        Err(err) => parse_error(err, &filepath),
+    }
+}
+
+/// Saves a note, creating it along with its directories if needed.
+pub async fn put_note(
+    State(state): State<crate::http::ServerState>,
+    UrlPath(path): UrlPath<String>,
+    headers: HeaderMap,
+    Json(body): Json<Content>,
+) -> Response {
+    if !path.ends_with(".md") {
+        return (StatusCode::BAD_REQUEST, "Notes must have the .md extension").into_response();
+    }
+    let Some(filepath) = crate::http::resolve_new_path(&state.config, &path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if filepath.is_dir() {
+        return (StatusCode::CONFLICT, "A directory exists at this path").into_response();
+    }
+
+    let _writing = state.writing.lock().await;
+    let current = match crate::note::read_existing(&filepath) {
+        Ok(current) => current,
+        Err(err) => return io_error(err, &filepath),
+    };
+    if let Some(status) = precondition_failed(&headers, current.as_deref()) {
+        return status.into_response();
+    }
+
+    match crate::note::replace(&filepath, body.content.as_bytes(), current.as_deref()) {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                "The note changed while it was written",
+            )
+                .into_response()
+        }
+        Err(err) => return io_error(err, &filepath),
+    }
+
+    let status = match current {
+        Some(_) => StatusCode::OK,
+        None => StatusCode::CREATED,
+    };
+    let etag = etag(body.content.as_bytes());
+    match note(&state.config.root_dir, &path, body.content) {
+        Ok(note) => (status, [(header::ETAG, etag)], Json(note)).into_response(),
+        Err(err) => parse_error(err, &filepath),
+    }
+}
+
+/// Moves a note to the trash directory, keeping its path relative to the root directory.
+pub async fn delete_note(
+    State(state): State<crate::http::ServerState>,
+    UrlPath(path): UrlPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(filepath) = note_path(&state.config, &path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let _writing = state.writing.lock().await;
+    let current = match std::fs::read(&filepath) {
+        Ok(content) => content,
+        Err(err) => return io_error(err, &filepath),
+    };
+    if let Some(status) = precondition_failed(&headers, Some(&current)) {
+        return status.into_response();
+    }
+
+    let trash_dir = state.config.root_dir.join(TRASH_DIR);
+    let mut trashed = trash_dir.join(crate::http::relative_path(
+        &state.config.root_dir,
+        &filepath,
+    ));
+    if trashed.exists() {
+        let suffix = chrono::Local::now().format("%Y%m%d%H%M%S");
+        trashed.set_extension(format!("{}.md", suffix));
+    }
+
+    let moved = trashed
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::rename(&filepath, &trashed));
+    match moved {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => io_error(err, &filepath),
+    }
+}
+
+/// Serves the journal of a period, e.g. `/api/journal/weekly/today`.
+pub async fn get_journal(
+    State(state): State<crate::http::ServerState>,
+    UrlPath((period, date)): UrlPath<(String, String)>,
+) -> Response {
+    let Ok(period) = period.parse::<crate::journal::Period>() else {
+        return (StatusCode::NOT_FOUND, "Unknown period").into_response();
+    };
+    let date = match crate::date::parse(&date, state.config.journal.weeks()) {
+        Ok(date) => date,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+
+    let cx = crate::Context {
+        config: state.config.clone(),
+        documents: state.documents.clone(),
+    };
+    let paths = crate::journal::paths(&cx, date, &[period], Path::new(""));
+    let Some(path) = paths.first() else {
+        return (StatusCode::NOT_FOUND, "Unknown period").into_response();
+    };
+    let path = path.to_string_lossy().replace('\\', "/");
+
+    let filepath = note_path(&state.config, &path);
+    let (note, error) = match &filepath {
+        Some(filepath) => match std::fs::read_to_string(filepath)
+            .map_err(Into::into)
+            .and_then(|content| note(&state.config.root_dir, &path, content))
+        {
+            Ok(note) => (Some(note), None),
+            Err(err) => {
+                log::error!("{}: Unable to parse note {}", err, filepath.display());
+                (None, Some(String::from("Unable to parse note")))
+            }
+        },
+        None => (None, None),
+    };
+    Json(Journal {
+        exists: filepath.is_some(),
+        path,
+        note,
+        error,
+    })
+    .into_response()
+}
+
+async fn backlinks(state: &crate::http::ServerState, path: &str) -> Response {
+    let config = &state.config;
+    let Some(filepath) = note_path(config, path) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let note = match crate::Note::build(&config.root_dir.join(path.trim_start_matches('/'))) {
+        Ok(note) => note,
+        Err(err) => return parse_error(err, &filepath),
+    };
+
+    let root_dir = config.root_dir.clone();
+    let backlinks: Vec<Backlink> = crate::http::with_visible_notes(state, move |notes| {
+        note.backlinks(notes.iter().copied(), &root_dir)
+            .into_iter()
+            .map(|(source, link)| Backlink {
+                path: crate::http::relative_path(&root_dir, &source.path),
+                title: source.title.clone(),
+                link: api_link(&root_dir, link),
+            })
+            .collect()
+    })
+    .await;
+
+    Json(backlinks).into_response()
+}
+
+fn note(root_dir: &Path, path: &str, content: String) -> crate::Result<Note> {
+    let path = path.trim_start_matches('/');
+    let note = crate::Note::build_from_str(&root_dir.join(path), &content)?;
+    Ok(Note {
+        links: Some(
+            note.links()
+                .iter()
+                .map(|link| api_link(root_dir, link))
+                .collect(),
+        ),
+        content: Some(content),
+        ..Note::summary(root_dir, &note)
+    })
+}
+
+/// Resolves the path of an existing note.
+fn note_path(config: &crate::Config, path: &str) -> Option<std::path::PathBuf> {
+    crate::http::resolve_path(config, path)
+        .filter(|filepath| filepath.is_file() && filepath.extension().is_some_and(|e| e == "md"))
+}
+
+/// Link whose URL, if it points to a file, is relative to `root_dir` instead of absolute.
+fn api_link(root_dir: &Path, link: &crate::Link) -> crate::Link {
+    let Some(target) = link.target(root_dir) else {
+        return link.clone();
+    };
+    let suffix = link.url.find(['#', '?']).map_or("", |pos| &link.url[pos..]);
+    crate::Link {
+        url: format!(
+            "{}{}",
+            crate::http::relative_path(root_dir, &target),
+            suffix
+        ),
+        ..link.clone()
+    }
+}
+
+fn etag(content: &[u8]) -> String {
+    format!("\"{}\"", crate::auth::sha256_hex(content))
+}
+
+/// Whether the header lists `etag`, or is `*`.
+fn header_matches(headers: &HeaderMap, name: header::HeaderName, etag: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|value| value.trim().trim_start_matches("W/"))
+        .any(|value| value == "*" || value == etag)
+}
+
+/// Checks `If-Match` and `If-None-Match` against the current content of a note, if it exists.
+fn precondition_failed(headers: &HeaderMap, current: Option<&[u8]>) -> Option<StatusCode> {
+    let failed = match current {
+        Some(current) => {
+            let etag = etag(current);
+            (headers.contains_key(header::IF_MATCH)
+                && !header_matches(headers, header::IF_MATCH, &etag))
+                || header_matches(headers, header::IF_NONE_MATCH, &etag)
+        }
+        None => headers.contains_key(header::IF_MATCH),
+    };
+
+    failed.then_some(StatusCode::PRECONDITION_FAILED)
+}
+
+fn io_error(err: std::io::Error, filepath: &Path) -> Response {
+    log::error!("{}: Unable to access file {}", err, filepath.display());
+    (StatusCode::INTERNAL_SERVER_ERROR, "Unable to access file").into_response()
+}
+
+fn parse_error(err: Box<dyn std::error::Error>, filepath: &Path) -> Response {
+    log::error!("{}: Unable to parse note {}", err, filepath.display());
+    (StatusCode::INTERNAL_SERVER_ERROR, "Unable to parse note").into_response()
+}
+
+#[cfg(test)]
+mod api_tests {
+    use super::*;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn vault() -> crate::Result<(tempfile::TempDir, crate::Context)> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("journals"))?;
+        std::fs::write(
+            dir.path().join("a.md"),
+            "---\ntags: [work, rust]\n---\n# A\n\nSee [B](b.md).\n",
+        )?;
+        std::fs::write(dir.path().join("b.md"), "---\ntags: work\n---\n# B\n")?;
+        std::fs::write(dir.path().join("journals/2023-10-21.md"), "# Saturday\n")?;
+        std::fs::write(dir.path().join(".hidden.md"), "---\ntags: secret\n---\n")?;
+
+        let mut cx = crate::Context::default();
+        cx.config.root_dir = dir.path().to_path_buf();
+        Ok((dir, cx))
+    }
+
+    async fn request(
+        cx: &crate::Context,
+        method: &str,
+        uri: &str,
+        headers: &[(header::HeaderName, &str)],
+        body: &str,
+    ) -> (StatusCode, HeaderMap, serde_json::Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        if !body.is_empty() {
+            request = request.header(header::CONTENT_TYPE, "application/json");
+        }
+        let request = request
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+
+        let response = crate::http::router(cx.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, headers, json)
+    }
+
+    #[tokio::test]
+    async fn lists() -> crate::Result<()> {
+        let (_dir, cx) = vault()?;
+
+        let (_, _, notes) = request(&cx, "GET", "/api/notes?tag=work", &[], "").await;
+        let paths: Vec<&str> = notes
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|note| note["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(vec!["a.md", "b.md"], paths);
+
+        let (_, _, notes) = request(&cx, "GET", "/api/notes?dir=journals", &[], "").await;
+        assert_eq!("Saturday", notes[0]["title"]);
+
+        let (_, _, tags) = request(&cx, "GET", "/api/tags", &[], "").await;
+        assert_eq!(serde_json::json!({"rust": 1, "work": 2}), tags);
+
+        std::fs::write(cx.config.root_dir.join("c.md"), "---\ntags: rust\n---\n")?;
+        std::fs::create_dir_all(cx.config.root_dir.join(TRASH_DIR))?;
+        std::fs::write(
+            cx.config.root_dir.join(TRASH_DIR).join("d.md"),
+            "---\ntags: rust\n---\n",
+        )?;
+        let (_, _, tags) = request(&cx, "GET", "/api/tags", &[], "").await;
+        assert_eq!(serde_json::json!({"rust": 2, "work": 2}), tags);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reads() -> crate::Result<()> {
+        let (dir, cx) = vault()?;
+
+        let (status, headers, note) = request(&cx, "GET", "/api/notes/a.md", &[], "").await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!("A", note["title"]);
+        assert_eq!(serde_json::json!(["work", "rust"]), note["tags"]);
+        assert_eq!("b.md", note["links"][0]["url"]);
+        let etag = headers[header::ETAG].to_str()?;
+
+        let (status, _, _) = request(
+            &cx,
+            "GET",
+            "/api/notes/a.md",
+            &[(header::IF_NONE_MATCH, etag)],
+            "",
+        )
+        .await;
+        assert_eq!(StatusCode::NOT_MODIFIED, status);
+
+        let (_, _, backlinks) = request(&cx, "GET", "/api/notes/b.md/backlinks", &[], "").await;
+        assert_eq!("a.md", backlinks[0]["path"]);
+        assert_eq!("See B.", backlinks[0]["link"]["context"]);
+
+        let (_, _, journal) = request(&cx, "GET", "/api/journal/daily/2023-10-21", &[], "").await;
+        assert_eq!("journals/2023-10-21.md", journal["path"]);
+        assert_eq!("Saturday", journal["note"]["title"]);
+        assert_eq!(true, journal["exists"]);
+
+        std::fs::write(dir.path().join("journals/2023-10-22.md"), b"# \xff\n")?;
+        let (_, _, journal) = request(&cx, "GET", "/api/journal/daily/2023-10-22", &[], "").await;
+        assert_eq!(true, journal["exists"]);
+        assert_eq!("Unable to parse note", journal["error"]);
+        assert!(journal.get("note").is_none());
+
+        let (_, _, journal) = request(&cx, "GET", "/api/journal/daily/2023-10-23", &[], "").await;
+        assert_eq!(false, journal["exists"]);
+        assert!(journal.get("error").is_none());
+
+        for uri in [
+            "/api/notes/.hidden.md",
+            "/api/notes/../a.md",
+            "/api/notes/c.md",
+        ] {
+            assert_eq!(
+                StatusCode::NOT_FOUND,
+                request(&cx, "GET", uri, &[], "").await.0
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn writes() -> crate::Result<()> {
+        let (dir, cx) = vault()?;
+        let body = r##"{"content": "# New\n"}"##;
+
+        let (status, headers, note) = request(&cx, "PUT", "/api/notes/new/c.md", &[], body).await;
+        assert_eq!(StatusCode::CREATED, status);
+        assert_eq!("New", note["title"]);
+        assert_eq!(
+            "# New\n",
+            std::fs::read_to_string(dir.path().join("new/c.md"))?
+        );
+        let etag = headers[header::ETAG].to_str()?.to_string();
+
+        let stale = [(header::IF_MATCH, "\"stale\"")];
+        let (status, _, _) = request(&cx, "PUT", "/api/notes/new/c.md", &stale, body).await;
+        assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+        let (status, _, _) = request(&cx, "DELETE", "/api/notes/new/c.md", &stale, "").await;
+        assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+
+        let fresh = [(header::IF_MATCH, etag.as_str())];
+        let (status, _, _) = request(&cx, "PUT", "/api/notes/new/c.md", &fresh, body).await;
+        assert_eq!(StatusCode::OK, status);
+
+        let (status, _, _) = request(&cx, "DELETE", "/api/notes/new/c.md", &fresh, "").await;
+        assert_eq!(StatusCode::NO_CONTENT, status);
+        assert!(!dir.path().join("new/c.md").exists());
+        assert!(dir.path().join(".trash/new/c.md").exists());
+
+        for uri in [
+            "/api/notes/.git/c.md",
+            "/api/notes/../c.md",
+            "/api/notes/c.txt",
+        ] {
+            let (status, _, _) = request(&cx, "PUT", uri, &[], body).await;
+            assert_ne!(StatusCode::OK, status);
+            assert_ne!(StatusCode::CREATED, status);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn overwrites_binary_notes() -> crate::Result<()> {
+        let (dir, cx) = vault()?;
+        let bytes = b"# Latin-1 caf\xe9\n";
+        std::fs::write(dir.path().join("latin.md"), bytes)?;
+        let body = r##"{"content": "# Caf\u00e9\n"}"##;
+
+        let absent = [(header::IF_NONE_MATCH, "*")];
+        let (status, _, _) = request(&cx, "PUT", "/api/notes/latin.md", &absent, body).await;
+        assert_eq!(StatusCode::PRECONDITION_FAILED, status);
+        assert_eq!(
+            bytes,
+            std::fs::read(dir.path().join("latin.md"))?.as_slice()
+        );
+
+        let etag = etag(bytes);
+        let fresh = [(header::IF_MATCH, etag.as_str())];
+        let (status, _, _) = request(&cx, "PUT", "/api/notes/latin.md", &fresh, body).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(
+            "# Café\n",
+            std::fs::read_to_string(dir.path().join("latin.md"))?
+        );
+        let names: Vec<_> = std::fs::read_dir(dir.path())?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(names.is_empty());
+        Ok(())
+    }
+}
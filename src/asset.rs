@@ -0,0 +1,304 @@
+// Serving of the files of the vault and of the rendered pages with HTTP caching: validators for
+// conditional requests, and ranges so that media can be seeked. Compression is left to the
+// layer of the router, see `compressible`.
+
+use std::io::SeekFrom;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Serves a file with its validators, honouring conditional and range requests. Files of unknown
+/// type are served as `application/octet-stream`.
+pub(crate) async fn serve_file(filepath: &Path, headers: &HeaderMap) -> Response {
+    let content_type = mime_guess::from_path(filepath)
+        .first_raw()
+        .unwrap_or("application/octet-stream");
+
+    let mut file = match tokio::fs::File::open(filepath).await {
+        Ok(file) => file,
+        Err(_) => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+    let metadata = match file.metadata().await {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            log::error!("{}: Unable to read metadata of {}", err, filepath.display());
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Unable to read file").into_response();
+        }
+    };
+
+    let len = metadata.len();
+    let modified = metadata.modified().ok();
+    let etag = file_etag(len, modified);
+
+    let mut response_headers = validators(&etag, modified);
+    if is_not_modified(headers, &etag, modified) {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(content_type).unwrap(),
+    );
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok())
+        .filter(|_| if_range_matches(headers, &etag, modified))
+        .and_then(|range| parse_range(range, len));
+
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, len),
+        Some(Ok((start, end))) => {
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end - 1, len)).unwrap(),
+            );
+            (StatusCode::PARTIAL_CONTENT, start, end)
+        }
+        Some(Err(())) => {
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes */{}", len)).unwrap(),
+            );
+            return (StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response();
+        }
+    };
+
+    if start > 0 {
+        if let Err(err) = file.seek(SeekFrom::Start(start)).await {
+            log::error!("{}: Unable to seek in {}", err, filepath.display());
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Unable to read file").into_response();
+        }
+    }
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(end - start));
+
+    let stream = tokio_util::io::ReaderStream::new(file.take(end - start));
+    let body = axum::body::StreamBody::new(stream);
+    (status, response_headers, body).into_response()
+}
+
+/// Serves content generated by the server, e.g. a rendered note, letting the clients revalidate
+/// their copy with its ETag.
+pub(crate) fn serve_generated(
+    headers: &HeaderMap,
+    content_type: &'static str,
+    content: String,
+) -> Response {
+    let etag = format!("\"{}\"", crate::auth::sha256_hex(content.as_bytes()));
+    let mut response_headers = validators(&etag, None);
+    if is_not_modified(headers, &etag, None) {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    (response_headers, content).into_response()
+}
+
+/// Same as [`serve_generated`] for an HTML page.
+pub(crate) fn serve_html(headers: &HeaderMap, html: String) -> Response {
+    serve_generated(headers, "text/html; charset=utf-8", html)
+}
+
+/// Whether the router should compress a response: text that isn't already a partial content.
+/// Server-sent events, images and small bodies are excluded by the default predicate.
+pub(crate) fn compressible(
+    status: StatusCode,
+    _: axum::http::Version,
+    headers: &HeaderMap,
+    _: &axum::http::Extensions,
+) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+
+    status != StatusCode::PARTIAL_CONTENT
+        && (content_type.starts_with("text/")
+            || content_type.starts_with("application/json")
+            || content_type.starts_with("application/javascript")
+            || content_type.starts_with("application/xml")
+            || content_type.starts_with("image/svg+xml"))
+}
+
+/// ETag derived from the size and modification time, so that files are not read to be hashed.
+fn file_etag(len: u64, modified: Option<SystemTime>) -> String {
+    let modified = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+    format!("\"{:x}-{:x}\"", len, modified)
+}
+
+fn validators(etag: &str, modified: Option<SystemTime>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ETAG, HeaderValue::from_str(etag).unwrap());
+    // Cache, but check that the copy is still fresh as notes change often.
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if let Some(modified) = modified {
+        headers.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_str(&httpdate::fmt_http_date(modified)).unwrap(),
+        );
+    }
+    headers
+}
+
+/// Whether the copy of the client is fresh. `If-None-Match` takes precedence over
+/// `If-Modified-Since`.
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        return if_none_match.to_str().is_ok_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        });
+    }
+
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|since| since.to_str().ok())
+        .and_then(|since| httpdate::parse_http_date(since).ok());
+    match (since, modified) {
+        // HTTP dates have a precision of a second.
+        (Some(since), Some(modified)) => httpdate::HttpDate::from(modified) <= since.into(),
+        _ => false,
+    }
+}
+
+/// Whether the range applies to the current version of the file, according to `If-Range`.
+fn if_range_matches(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    let Some(if_range) = headers
+        .get(header::IF_RANGE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return true;
+    };
+
+    if if_range.starts_with('"') {
+        return if_range == etag;
+    }
+    match (httpdate::parse_http_date(if_range), modified) {
+        (Ok(date), Some(modified)) => {
+            httpdate::HttpDate::from(modified) == httpdate::HttpDate::from(date)
+        }
+        _ => false,
+    }
+}
+
+/// Parses a `Range` header into the start and the exclusive end of the bytes to send, or an
+/// error if it can't be satisfied. Returns `None` for ranges that aren't supported, in which case
+/// the whole file is sent.
+fn parse_range(range: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let range = range.trim().strip_prefix("bytes=")?;
+    // Multiple ranges are rarely used by media players.
+    if range.contains(',') {
+        return None;
+    }
+    let (start, end) = range.trim().split_once('-')?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return None,
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 || len == 0 {
+                return Some(Err(()));
+            }
+            (len.saturating_sub(suffix), len)
+        }
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end = match end {
+                "" => len,
+                end => end.parse::<u64>().ok()?.saturating_add(1).min(len),
+            };
+            if start >= len || start >= end {
+                return Some(Err(()));
+            }
+            (start, end)
+        }
+    };
+
+    Some(Ok((start, end)))
+}
+
+#[cfg(test)]
+mod asset_tests {
+    use super::*;
+
+    async fn body(response: Response) -> String {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(Some(Ok((0, 10))), parse_range("bytes=0-", 10));
+        assert_eq!(Some(Ok((2, 5))), parse_range("bytes=2-4", 10));
+        assert_eq!(Some(Ok((7, 10))), parse_range("bytes=-3", 10));
+        assert_eq!(Some(Ok((5, 10))), parse_range("bytes=5-100", 10));
+        assert_eq!(Some(Err(())), parse_range("bytes=10-", 10));
+        assert_eq!(Some(Err(())), parse_range("bytes=-0", 10));
+        assert_eq!(None, parse_range("bytes=0-1,3-4", 10));
+        assert_eq!(None, parse_range("lines=1-2", 10));
+    }
+
+    #[tokio::test]
+    async fn serves_files() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let filepath = dir.path().join("data.unknownext");
+        std::fs::write(&filepath, "0123456789")?;
+
+        let response = serve_file(&filepath, &HeaderMap::new()).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "application/octet-stream",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+        let etag = response.headers()[header::ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.clone());
+        let response = serve_file(&filepath, &headers).await;
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=2-4"));
+        let response = serve_file(&filepath, &headers).await;
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        assert_eq!("bytes 2-4/10", response.headers()[header::CONTENT_RANGE]);
+        assert_eq!("234", body(response).await);
+
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"stale\""));
+        let response = serve_file(&filepath, &headers).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("0123456789", body(response).await);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=20-"));
+        let response = serve_file(&filepath, &headers).await;
+        assert_eq!(StatusCode::RANGE_NOT_SATISFIABLE, response.status());
+        Ok(())
+    }
+
+    #[test]
+    fn revalidates_generated_content() {
+        let response = serve_html(&HeaderMap::new(), String::from("<p>Note</p>"));
+        assert_eq!(StatusCode::OK, response.status());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            response.headers()[header::ETAG].clone(),
+        );
+        let response = serve_html(&headers, String::from("<p>Note</p>"));
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+    }
+}
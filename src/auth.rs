@@ -0,0 +1,157 @@
+// Middleware guarding the HTTP server: authentication against the credentials of the
+// configuration and the read-only mode. Excluded directories are handled by `resolve_path` so
+// that they are also left out of listings and search results.
+
+use argon2::password_hash::{PasswordHash, PasswordVerifier};
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+pub async fn authenticate(
+    State(state): State<crate::http::ServerState>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let auth = &state.config.http.auth;
+    if !auth.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    if let Some(authorization) = authorization {
+        if is_known(&state, &authorization).await {
+            return next.run(request).await;
+        }
+    }
+
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Basic realm=\"noteutil\"")],
+        "Authentication required",
+    )
+        .into_response()
+}
+
+pub async fn reject_writes(
+    State(state): State<crate::http::ServerState>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    // Moving the cursor of the preview doesn't modify anything.
+    let is_write = !matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) && !request.uri().path().starts_with("/_cursor/");
+
+    if state.config.http.read_only && is_write {
+        return (StatusCode::FORBIDDEN, "The server is read-only").into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Whether the authorization header is valid. The slow hashes of the credentials are verified once
+/// per header, on a thread where blocking is allowed.
+async fn is_known(state: &crate::http::ServerState, authorization: &str) -> bool {
+    let key = sha256_hex(authorization.as_bytes());
+    let authorized = state.authorized.clone();
+    if authorized.lock().unwrap().contains(&key) {
+        return true;
+    }
+
+    let auth = state.config.http.auth.clone();
+    let authorization = String::from(authorization);
+    let valid = tokio::task::spawn_blocking(move || is_authorized(&auth, &authorization))
+        .await
+        .unwrap_or(false);
+    if valid {
+        authorized.lock().unwrap().insert(key);
+    }
+    valid
+}
+
+fn is_authorized(auth: &crate::Auth, authorization: &str) -> bool {
+    let Some((scheme, credentials)) = authorization.trim().split_once(' ') else {
+        return false;
+    };
+
+    if scheme.eq_ignore_ascii_case("bearer") {
+        let token = credentials.trim();
+        return auth.tokens.iter().any(|hash| verify(token, hash));
+    }
+
+    if scheme.eq_ignore_ascii_case("basic") {
+        let Ok(credentials) = STANDARD.decode(credentials.trim()) else {
+            return false;
+        };
+        let Some((user, password)) = std::str::from_utf8(&credentials)
+            .ok()
+            .and_then(|credentials| credentials.split_once(':'))
+        else {
+            return false;
+        };
+        return auth
+            .users
+            .get(user)
+            .is_some_and(|hash| verify(password, hash));
+    }
+
+    false
+}
+
+/// SHA-256 of `data` in lowercase hexadecimal.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    use sha2::Digest;
+    let digest = sha2::Sha256::digest(data);
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether `password` matches `hash`, a PHC string such as `$argon2id$v=19$...`.
+fn verify(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash.trim()).is_ok_and(|hash| {
+        argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+#[cfg(test)]
+mod auth_tests {
+    use super::*;
+
+    // "password" and "test", hashed with small parameters to keep the tests fast.
+    const PASSWORD_HASH: &str =
+        "$argon2id$v=19$m=8,t=1,p=1$c2FsdHNhbHRzYWx0$QLtm4XOGDJ9bF3tTthMuN0+J8bcuQpIWcGkIs+8cWSs";
+    const TOKEN_HASH: &str =
+        "$argon2id$v=19$m=8,t=1,p=1$dG9rZW5zYWx0c2FsdA$GHs8ZuYSmubytq+AfbSpYjyX4NEMUm9XckOFGwR1cRU";
+
+    fn auth() -> crate::Auth {
+        let mut auth = crate::Auth::default();
+        auth.users
+            .insert(String::from("alice"), String::from(PASSWORD_HASH));
+        auth.tokens.push(String::from(TOKEN_HASH));
+        auth
+    }
+
+    #[test]
+    fn authorizes() {
+        let auth = auth();
+        assert!(is_authorized(&auth, "Basic YWxpY2U6cGFzc3dvcmQ="));
+        assert!(is_authorized(&auth, "Bearer test"));
+
+        // alice:wrong
+        assert!(!is_authorized(&auth, "Basic YWxpY2U6d3Jvbmc="));
+        assert!(!is_authorized(&auth, "Bearer wrong"));
+        assert!(!is_authorized(&auth, "Digest test"));
+        assert!(!is_authorized(&auth, "Basic YWxpY2U6cGFzc3dvcmQ"));
+    }
+}
@@ -0,0 +1,212 @@
+// Month calendar of the journals, linking the days, weeks, month and year of the month to their
+// journals wherever the paths of `[journal.path]` put them.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::html::escape;
+use crate::journal::Period;
+
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Params {
+    /// Any date of the month, in the forms of `noteutil journal --date`, e.g. `2026-10`.
+    date: Option<String>,
+}
+
+pub async fn serve_calendar(
+    State(state): State<crate::http::ServerState>,
+    Query(params): Query<Params>,
+    headers: HeaderMap,
+) -> Response {
+    let ctx = crate::Context {
+        config: state.config.clone(),
+        documents: state.documents.clone(),
+    };
+    let today = chrono::Local::now().date_naive();
+    let date = match params.date.as_deref() {
+        Some(date) => match crate::date::parse(date, ctx.config.journal.weeks()) {
+            Ok(date) => date,
+            Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+        },
+        None => today,
+    };
+
+    let base_path = state.config.http.base_path();
+    let body = calendar(&ctx, &base_path, date, today);
+
+    let mut breadcrumbs = crate::html::breadcrumbs(&base_path, "");
+    breadcrumbs.push(crate::theme::Breadcrumb {
+        name: String::from("Journal"),
+        url: format!("{}/journal", base_path),
+    });
+    let page = crate::theme::Page {
+        title: date.format("%B %Y").to_string(),
+        body,
+        breadcrumbs,
+        ..Default::default()
+    };
+    crate::asset::serve_html(&headers, crate::theme::render(&state.config, &page))
+}
+
+/// HTML table of the month of `date`, with a row per week.
+fn calendar(ctx: &crate::Context, base_path: &str, date: NaiveDate, today: NaiveDate) -> String {
+    let week_start = ctx.config.journal.week_start;
+    let first = Period::Monthly.start(ctx, date);
+    let last = Period::Monthly.end(ctx, date);
+    let month_url =
+        |date: NaiveDate| format!("{}/journal?date={}", base_path, date.format("%Y-%m"));
+
+    let mut body = String::from("<table class=\"calendar\">\n");
+    body.push_str(&format!(
+        "<caption><a class=\"calendar-previous\" href=\"{}\">←</a> {} {} \
+         <a class=\"calendar-next\" href=\"{}\">→</a></caption>\n",
+        escape(&month_url(first - Duration::days(1))),
+        link(
+            ctx,
+            base_path,
+            &Period::Monthly,
+            date,
+            &date.format("%B").to_string()
+        ),
+        link(
+            ctx,
+            base_path,
+            &Period::Yearly,
+            date,
+            &date.year().to_string()
+        ),
+        escape(&month_url(last + Duration::days(1))),
+    ));
+
+    body.push_str("<thead><tr><th class=\"calendar-week\">Week</th>");
+    let mut day = crate::date::start_of_week(first, week_start);
+    for _ in 0..7 {
+        body.push_str(&format!("<th>{}</th>", day.format("%a")));
+        day += Duration::days(1);
+    }
+    body.push_str("</tr></thead>\n<tbody>\n");
+
+    let mut week = crate::date::start_of_week(first, week_start);
+    while week <= last {
+        let number = crate::date::week_number(week, ctx.config.journal.weeks());
+        body.push_str(&format!(
+            "<tr><th class=\"calendar-week\">{}</th>",
+            link(ctx, base_path, &Period::Weekly, week, &number.to_string())
+        ));
+        for day in week.iter_days().take(7) {
+            let mut classes = Vec::new();
+            if day.month() != first.month() {
+                classes.push("outside");
+            }
+            if day == today {
+                classes.push("today");
+            }
+            body.push_str(&format!(
+                "<td class=\"{}\">{}</td>",
+                classes.join(" "),
+                link(ctx, base_path, &Period::Daily, day, &day.day().to_string())
+            ));
+        }
+        body.push_str("</tr>\n");
+        week += Duration::weeks(1);
+    }
+
+    body.push_str("</tbody>\n</table>\n");
+    body
+}
+
+/// Link to the journal of `period` for `date`, with the `exists` class when there is one.
+fn link(
+    ctx: &crate::Context,
+    base_path: &str,
+    period: &Period,
+    date: NaiveDate,
+    text: &str,
+) -> String {
+    let Some(path) = period.path(ctx, date) else {
+        return escape(text);
+    };
+    // Journals the server doesn't serve are neither linked nor told apart.
+    let Some(filepath) = crate::http::resolve_new_path(&ctx.config, &path) else {
+        return escape(text);
+    };
+    let class = match filepath.is_file() {
+        true => " class=\"exists\"",
+        false => "",
+    };
+    format!(
+        "<a{} href=\"{}\">{}</a>",
+        class,
+        escape(&crate::html::page_url(base_path, &path)),
+        escape(text)
+    )
+}
+
+#[cfg(test)]
+mod calendar_tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn renders_months() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journals = dir.path().join("journals");
+        std::fs::create_dir_all(&journals)?;
+        std::fs::write(journals.join("2023-10-16.md"), "")?;
+        std::fs::write(journals.join("2023-w42.md"), "")?;
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+        let html = calendar(&ctx, "", date(2023, 10, 21), date(2023, 10, 17));
+
+        assert!(html.contains(r#"<a href="/pages/journals/2023-10.md">October</a>"#));
+        assert!(html.contains(r#"<a href="/pages/journals/2023.md">2023</a>"#));
+        assert!(html.contains(r#"href="/journal?date=2023-09">←</a>"#));
+        assert!(html.contains(r#"href="/journal?date=2023-11">→</a>"#));
+        assert!(html.contains(
+            r#"<th class="calendar-week"><a class="exists" href="/pages/journals/2023-w42.md">42</a></th>"#
+        ));
+        assert!(html.contains(
+            r#"<td class=""><a class="exists" href="/pages/journals/2023-10-16.md">16</a></td>"#
+        ));
+        assert!(html
+            .contains(r#"<td class="today"><a href="/pages/journals/2023-10-17.md">17</a></td>"#));
+        // October 2023 starts on a Sunday and ends on a Tuesday.
+        assert!(html.contains(
+            "<tbody>\n<tr><th class=\"calendar-week\"><a href=\"/pages/journals/2023-w40.md\">40</a>\
+             </th><td class=\"\"><a href=\"/pages/journals/2023-10-01.md\">1</a>"
+        ));
+        assert!(html.contains(
+            r#"<td class="outside"><a href="/pages/journals/2023-11-04.md">4</a></td></tr>"#
+        ));
+        assert_eq!(5, html.matches("</td></tr>").count());
+
+        ctx.config.http.exclude = vec![String::from("journals")];
+        let html = calendar(&ctx, "", date(2023, 10, 21), date(2023, 10, 17));
+        assert!(html.contains(r#"<td class="">16</td>"#));
+        assert!(!html.contains("exists"));
+        Ok(())
+    }
+
+    #[test]
+    fn numbers_weeks_like_their_journals() {
+        let mut ctx = crate::Context::default();
+        ctx.config.journal.week_start = chrono::Weekday::Mon;
+        ctx.config.journal.path.weekly = String::from("journals/%Y-w%W.md");
+        // 2020 starts on a Wednesday, in the ISO week 1 but in the week 0 of `%W`.
+        let html = calendar(&ctx, "", date(2020, 10, 5), date(2020, 10, 5));
+        assert!(html.contains(r#"<a href="/pages/journals/2020-w40.md">40</a>"#));
+
+        ctx.config.journal.path.weekly = String::from("journals/%G-W%V.md");
+        let html = calendar(&ctx, "", date(2020, 10, 5), date(2020, 10, 5));
+        assert!(html.contains(r#"<a href="/pages/journals/2020-W41.md">41</a>"#));
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use noteutil::export::Filter;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(subcommand)]
+    format: Format,
+}
+
+#[derive(clap::Subcommand)]
+enum Format {
+    /// Render the notes as a static site.
+    Html(HtmlArgs),
+}
+
+#[derive(clap::Args)]
+struct HtmlArgs {
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Only export the notes having this tag. Can be repeated.
+    #[arg(long)]
+    tag: Vec<String>,
+
+    /// Only export the notes whose front matter has this value, e.g. `publish=true`. Can be
+    /// repeated.
+    #[arg(long = "where", value_parser = parse_condition)]
+    conditions: Vec<(String, String)>,
+}
+
+pub fn run(ctx: &noteutil::Context, args: &Args) -> noteutil::Result<()> {
+    match &args.format {
+        Format::Html(args) => {
+            let filter = Filter {
+                tags: args.tag.clone(),
+                front_matter: args.conditions.clone(),
+            };
+            let summary = noteutil::export::html(&ctx.config, &args.out, &filter)?;
+            println!(
+                "Exported {} notes and {} assets to {}",
+                summary.notes,
+                summary.assets,
+                args.out.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_condition(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+        None => Err(format!("Expected key=value: {}", s)),
+    }
+}
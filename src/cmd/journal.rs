@@ -1,22 +1,165 @@
+use std::error::Error;
+use std::path::PathBuf;
+
 #[derive(clap::Args, Default)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// `daily`, `weekly`, `monthly`, `quarterly`, `yearly` or a period of the configuration.
     #[arg(short = 'p', long = "period")]
     periods: Vec<noteutil::journal::Period>,
 
     #[arg(long)]
     date: Option<String>,
+
+    /// Lists the journals from this date to `--to`, or to `--date`.
+    #[arg(long, value_name = "DATE")]
+    from: Option<String>,
+
+    /// Lists the journals from `--from`, or from `--date`, to this date.
+    #[arg(long, value_name = "DATE")]
+    to: Option<String>,
+
+    /// Only prints the journals that exist.
+    #[arg(long, conflicts_with = "missing")]
+    existing: bool,
+
+    /// Only prints the journals that don't exist.
+    #[arg(long)]
+    missing: bool,
+
+    /// Moves the date by this number of periods, e.g. `-1` for the previous day, week, month and
+    /// year.
+    #[arg(
+        long,
+        default_value_t = 0,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["from", "to"]
+    )]
+    offset: i32,
+
+    /// Creates the journals that don't exist, from the templates of `[journal.templates]`.
+    #[arg(long, conflicts_with_all = ["from", "to"])]
+    create: bool,
+
+    /// Same as `--create`, printing the paths of the journals to open them.
+    #[arg(long, conflicts_with_all = ["create", "from", "to"])]
+    open: bool,
+
+    /// Prints the period, the first and the last days of a journal.
+    #[arg(long, value_name = "PATH")]
+    which: Option<PathBuf>,
+
+    /// Prints the nearest existing journal of the same period before this one.
+    #[arg(long, value_name = "PATH", conflicts_with = "next")]
+    prev: Option<PathBuf>,
+
+    /// Prints the nearest existing journal of the same period after this one.
+    #[arg(long, value_name = "PATH")]
+    next: Option<PathBuf>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Summarize the journals of a period, e.g. the days of a week, with their completed tasks
+    /// and the sections of `[journal.review]`.
+    Review(ReviewArgs),
+}
+
+#[derive(clap::Args)]
+struct ReviewArgs {
+    #[arg(short = 'p', long, default_value = "weekly")]
+    period: noteutil::journal::Period,
+
+    #[arg(long, default_value = "today")]
+    date: String,
+
+    #[arg(short, long)]
+    outfile: Option<PathBuf>,
 }
 
-pub fn run(ctx: &noteutil::Context, args: &Args) {
+pub fn run(ctx: &noteutil::Context, args: &Args) -> Result<(), Box<dyn Error>> {
     let root_dir = &ctx.config.root_dir;
 
+    if let Some(Command::Review(args)) = &args.command {
+        return review(ctx, args);
+    }
+
+    if let Some(period) = args.periods.iter().find(|period| !period.is_known(ctx)) {
+        Err(format!("Unknown period: {}", period))?;
+    }
+
+    if let Some(path) = &args.which {
+        let (period, start, end) = noteutil::journal::which(ctx, path)
+            .ok_or_else(|| format!("Not a journal: {}", path.display()))?;
+        println!("{} {} {}", period, start, end);
+        return Ok(());
+    }
+    if let Some(path) = &args.prev {
+        let previous = noteutil::journal::previous(ctx, path)
+            .ok_or_else(|| format!("No journal before {}", path.display()))?;
+        println!("{}", previous.display());
+        return Ok(());
+    }
+    if let Some(path) = &args.next {
+        let next = noteutil::journal::next(ctx, path)
+            .ok_or_else(|| format!("No journal after {}", path.display()))?;
+        println!("{}", next.display());
+        return Ok(());
+    }
+
     let today = chrono::Local::now().date_naive();
+    let parse = |date: &str| noteutil::date::parse(date, ctx.config.journal.weeks());
     let date = match args.date.as_deref() {
-        Some(args_date) => noteutil::date::parse(args_date).expect("Invalid date"),
+        Some(args_date) => parse(args_date)?,
         None => today,
     };
 
-    for path in noteutil::journal::paths(ctx, date, &args.periods, root_dir) {
+    if args.create || args.open {
+        for period in noteutil::journal::selected(&args.periods) {
+            let date = period.offset(ctx, date, args.offset);
+            let path = noteutil::journal::create(ctx, period, date)?;
+            if args.open {
+                println!("{}", path.display());
+            }
+        }
+        return Ok(());
+    }
+
+    let paths = match (args.from.as_deref(), args.to.as_deref()) {
+        (None, None) => {
+            noteutil::journal::offset_paths(ctx, date, args.offset, &args.periods, root_dir)
+        }
+        (from, to) => {
+            let from = from.map_or(Ok(date), parse)?;
+            let to = to.map_or(Ok(date), parse)?;
+            noteutil::journal::range_paths(ctx, from, to, &args.periods, root_dir)
+        }
+    };
+
+    for path in paths {
+        if (args.existing && !path.exists()) || (args.missing && path.exists()) {
+            continue;
+        }
         println!("{}", path.display());
     }
+
+    Ok(())
+}
+
+fn review(ctx: &noteutil::Context, args: &ReviewArgs) -> Result<(), Box<dyn Error>> {
+    if !args.period.is_known(ctx) {
+        Err(format!("Unknown period: {}", args.period))?;
+    }
+
+    let date = noteutil::date::parse(&args.date, ctx.config.journal.weeks())?;
+    let summary = noteutil::journal::review(ctx, &args.period, date)?;
+    match &args.outfile {
+        Some(outfile) => std::fs::write(outfile, summary)?,
+        None => print!("{}", summary),
+    }
+
+    Ok(())
 }
@@ -1,23 +1,29 @@
 use std::error::Error;
 
+mod export;
 mod journal;
 mod note;
+mod search;
 mod server;
 mod template;
 
 #[derive(clap::Subcommand)]
 pub enum Command {
     Journal(journal::Args),
+    Export(export::Args),
     Template(template::Args),
     Note(note::Args),
+    Search(search::Args),
     Server(server::Args),
 }
 
 pub fn run(ctx: &noteutil::Context, cmd: &Option<Command>) -> Result<(), Box<dyn Error>> {
     match &cmd {
-        Some(Command::Journal(args)) => journal::run(&ctx, args),
-        Some(Command::Template(args)) => template::run(&ctx, args),
-        Some(Command::Note(args)) => note::run(&ctx, args),
+        Some(Command::Journal(args)) => journal::run(ctx, args)?,
+        Some(Command::Export(args)) => export::run(ctx, args)?,
+        Some(Command::Template(args)) => template::run(ctx, args)?,
+        Some(Command::Note(args)) => note::run(ctx, args),
+        Some(Command::Search(args)) => search::run(ctx, args),
         Some(Command::Server(args)) => server::run(ctx.clone(), args)?,
         None => {}
     }
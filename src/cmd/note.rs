@@ -27,10 +27,7 @@ pub fn run(ctx: &noteutil::Context, args: &Args) {
         .collect();
 
     if let Some(path) = args.link_to.as_ref() {
-        notes = notes
-            .into_iter()
-            .filter(|note| note.link_to(path))
-            .collect();
+        notes.retain(|note| note.link_to(path));
     }
 
     for note in notes {
@@ -0,0 +1,33 @@
+use noteutil::search::{Index, Search};
+
+#[derive(clap::Args, Default)]
+pub struct Args {
+    /// Words the notes must all contain.
+    query: Vec<String>,
+
+    /// Only search notes having this tag. Can be repeated.
+    #[arg(long)]
+    tag: Vec<String>,
+
+    /// Only search notes under this directory, relative to the root directory.
+    #[arg(long)]
+    dir: Option<String>,
+}
+
+/// Prints the hits as `path:line: snippet`, the default error format of the quickfix list.
+pub fn run(ctx: &noteutil::Context, args: &Args) {
+    let search = Search {
+        text: args.query.join(" "),
+        tags: args.tag.clone(),
+        dir: args.dir.clone(),
+    };
+
+    for hit in Index::build(&ctx.config.root_dir).search(&search) {
+        println!(
+            "{}:{}: {}",
+            ctx.config.root_dir.join(&hit.path).display(),
+            hit.line,
+            hit.snippet
+        );
+    }
+}
@@ -7,9 +7,18 @@ pub struct Args {
 
     #[arg(long)]
     http: bool,
+
+    /// Address the HTTP server binds to, e.g. 127.0.0.1:10428
+    #[arg(long)]
+    http_addr: Option<std::net::SocketAddr>,
 }
 
-pub fn run(cx: noteutil::Context, args: &Args) -> Result<(), Box<dyn Error>> {
+pub fn run(mut cx: noteutil::Context, args: &Args) -> Result<(), Box<dyn Error>> {
+    if let Some(addr) = args.http_addr {
+        cx.config.http.addr = addr.ip();
+        cx.config.http.port = addr.port();
+    }
+
     let rt = tokio::runtime::Runtime::new()?;
 
     let mut tasks = Vec::new();
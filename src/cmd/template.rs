@@ -9,9 +9,25 @@ pub struct Args {
 
     #[arg(short, long)]
     outfile: Option<PathBuf>,
+
+    /// Date of the note, in the forms of `noteutil journal --date`. Today by default.
+    #[arg(long)]
+    date: Option<String>,
+
+    /// Title of the note, the name of `--outfile` by default.
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Variable of the template, e.g. `project=noteutil`. Can be repeated.
+    #[arg(long = "var", value_parser = parse_var)]
+    vars: Vec<(String, String)>,
+
+    /// TOML file of variables of the template, overridden by `--var`.
+    #[arg(long = "vars", value_name = "FILE")]
+    vars_file: Option<PathBuf>,
 }
 
-pub fn run(ctx: &noteutil::Context, args: &Args) {
+pub fn run(ctx: &noteutil::Context, args: &Args) -> noteutil::Result<()> {
     let template_path = ctx
         .config
         .root_dir
@@ -19,13 +35,27 @@ pub fn run(ctx: &noteutil::Context, args: &Args) {
         .join(args.template.as_str());
 
     let mut tera = tera::Tera::default();
-    tera.add_template_file(template_path, Some(args.template.as_str()))
-        .expect("invalid template");
+    tera.add_template_file(template_path, Some(args.template.as_str()))?;
 
-    let context = tera::Context::new();
-    let content = tera
-        .render(args.template.as_str(), &context)
-        .expect("Unable to apply template");
+    let date = match args.date.as_deref() {
+        Some(date) => noteutil::date::parse(date, ctx.config.journal.weeks())?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let mut vars = match &args.vars_file {
+        Some(path) => noteutil::template::read_vars(path)?,
+        None => Default::default(),
+    };
+    for (key, value) in &args.vars {
+        vars.insert(key.clone(), toml::Value::String(value.clone()));
+    }
+    let target = noteutil::template::Target {
+        date,
+        title: args.title.clone(),
+        path: args.outfile.clone(),
+        vars,
+    };
+    let context = noteutil::template::context(ctx, &target)?;
+    let content = tera.render(args.template.as_str(), &context)?;
 
     match &args.outfile {
         Some(outfile) => {
@@ -35,4 +65,13 @@ pub fn run(ctx: &noteutil::Context, args: &Args) {
         }
         None => println!("{}", content),
     }
+
+    Ok(())
+}
+
+fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) => Ok((key.trim().to_string(), value.to_string())),
+        None => Err(format!("Expected key=value: {}", s)),
+    }
 }
@@ -2,26 +2,104 @@ use std::error::Error;
 use std::path::Path;
 use std::path::PathBuf;
 
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 #[serde(default)]
 pub struct Journal {
     pub path: JournalPath,
+    /// Periods other than the built-in ones, by name.
+    pub periods: std::collections::BTreeMap<String, CustomPeriod>,
+    /// First day of the weeks, Sunday like `%U` by default. It must agree with the week number of
+    /// the weekly path, Sunday for `%U` and Monday for `%W` and `%V`.
+    pub week_start: chrono::Weekday,
+    /// Templates of the new journals by period, relative to the `templates` directory.
+    pub templates: std::collections::BTreeMap<String, String>,
+    pub rollover: Rollover,
+    pub review: Review,
+}
+
+/// Carrying the unfinished tasks of the last daily journal over to a new one.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Rollover {
+    pub enabled: bool,
+    /// Heading of the section receiving the tasks, added if the journal doesn't have it.
+    pub section: String,
+    /// Replace the `[ ]` of the tasks carried over with `[>]` in the last journal.
+    pub mark_migrated: bool,
+}
+
+impl Default for Rollover {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            section: String::from("Tasks"),
+            mark_migrated: false,
+        }
+    }
+}
+
+/// Summaries of the journals of a period, e.g. the days of a week, by `noteutil journal review`.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Review {
+    /// Headings of the sections of the journals to gather, e.g. `Done` or `## Notes`.
+    pub sections: Vec<String>,
+    /// Template of the summary, relative to the `templates` directory.
+    pub template: Option<String>,
+}
+
+impl Journal {
+    /// How the weeks are numbered, after the weekly path.
+    pub fn weeks(&self) -> crate::date::Weeks {
+        crate::date::Weeks::new(&self.path.weekly, self.week_start)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let start = self.weeks().start();
+        if start != self.week_start {
+            return Err(format!(
+                "The weekly path {} has weeks starting on {} but week_start is {}",
+                self.path.weekly, start, self.week_start
+            )
+            .into());
+        }
+
+        for (name, period) in &self.periods {
+            if !matches!(name.parse(), Ok(crate::journal::Period::Custom(_))) {
+                return Err(format!("The period {} can't be redefined", name).into());
+            }
+            if period.path.trim().is_empty() {
+                return Err(format!("The period {} has no path", name).into());
+            }
+            if period.days == 0 && period.weeks == 0 {
+                return Err(format!("The period {} must last days or weeks", name).into());
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for Journal {
     fn default() -> Self {
         Self {
             path: JournalPath::default(),
+            periods: Default::default(),
+            week_start: chrono::Weekday::Sun,
+            templates: Default::default(),
+            rollover: Rollover::default(),
+            review: Review::default(),
         }
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 #[serde(default)]
 pub struct JournalPath {
     pub daily: String,
     pub weekly: String,
     pub monthly: String,
+    /// `%q` stands for the quarter.
+    pub quarterly: String,
     pub yearly: String,
 }
 
@@ -31,16 +109,164 @@ impl Default for JournalPath {
             daily: String::from("journals/%Y-%m-%d.md"),
             weekly: String::from("journals/%Y-w%U.md"),
             monthly: String::from("journals/%Y-%m.md"),
+            quarterly: String::from("journals/%Y-q%q.md"),
             yearly: String::from("journals/%Y.md"),
         }
     }
 }
 
-#[derive(serde::Deserialize, Debug, Clone)]
+/// A period of a fixed length, e.g. two-week sprints. Its journals are named after their first
+/// day.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+pub struct CustomPeriod {
+    pub path: String,
+    /// First day of any of the periods.
+    pub anchor: chrono::NaiveDate,
+    #[serde(default)]
+    pub days: u32,
+    #[serde(default)]
+    pub weeks: u32,
+}
+
+impl CustomPeriod {
+    /// Length of the period in days, at least one.
+    pub fn length(&self) -> i64 {
+        (i64::from(self.weeks) * 7 + i64::from(self.days)).max(1)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Symlinks {
+    /// Never serve a path going through a symbolic link.
+    Deny,
+    /// Serve symbolic links as long as they point inside `root_dir`.
+    #[default]
+    WithinRoot,
+    /// Follow symbolic links wherever they point to.
+    Follow,
+}
+
+/// Credentials accepted by the HTTP server. Anyone can access it when there are none.
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Auth {
+    /// Users of the HTTP basic authentication, mapped to the Argon2 hash of their password as a
+    /// PHC string, e.g. `$argon2id$v=19$m=19456,t=2,p=1$...`.
+    pub users: std::collections::BTreeMap<String, String>,
+    /// Argon2 hashes of the bearer tokens, as PHC strings.
+    pub tokens: Vec<String>,
+}
+
+impl Auth {
+    pub fn is_enabled(&self) -> bool {
+        !self.users.is_empty() || !self.tokens.is_empty()
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        for hash in self.users.values().chain(&self.tokens) {
+            argon2::password_hash::PasswordHash::new(hash.trim())
+                .map_err(|err| format!("Invalid password hash {}: {}", hash, err))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Http {
+    pub addr: std::net::IpAddr,
+    pub port: u16,
+    /// URL prefix the pages are served under, e.g. `/notes` behind a reverse proxy.
+    pub base_path: String,
+    pub symlinks: Symlinks,
+    /// Serve files and directories whose name starts with a dot. `.git` is never served.
+    pub serve_hidden: bool,
+    /// Note rendered at `/`, relative to `root_dir`. The root directory is listed if unset.
+    pub home_note: Option<String>,
+    /// Left out of the templates, like anything secret.
+    #[serde(skip_serializing)]
+    pub auth: Auth,
+    /// Reject every request modifying the notes.
+    pub read_only: bool,
+    /// Directories relative to `root_dir` that are never served, e.g. `private`.
+    pub exclude: Vec<String>,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            addr: std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
+            port: 10428,
+            base_path: String::new(),
+            symlinks: Symlinks::default(),
+            serve_hidden: false,
+            home_note: None,
+            auth: Auth::default(),
+            read_only: false,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl Http {
+    pub fn socket_addr(&self) -> std::net::SocketAddr {
+        std::net::SocketAddr::new(self.addr, self.port)
+    }
+
+    /// The base path with a leading slash and without a trailing one. Empty when the server is
+    /// mounted at the root.
+    pub fn base_path(&self) -> String {
+        let base_path = self.base_path.trim_matches('/');
+        if base_path.is_empty() {
+            return String::new();
+        }
+
+        format!("/{}", base_path)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Render {
+    /// Commands rendering the code blocks of a language to SVG, e.g. `dot = ["dot", "-Tsvg"]`.
+    /// They read the source of a block from stdin and write the SVG to stdout.
+    pub diagrams: std::collections::BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Render {
+    fn default() -> Self {
+        let dot = vec![String::from("dot"), String::from("-Tsvg")];
+        let mermaid = [
+            "mmdc",
+            "--input",
+            "-",
+            "--output",
+            "-",
+            "--outputFormat",
+            "svg",
+        ];
+        Self {
+            diagrams: [
+                (String::from("dot"), dot.clone()),
+                (String::from("graphviz"), dot),
+                (
+                    String::from("mermaid"),
+                    mermaid.iter().map(|arg| arg.to_string()).collect(),
+                ),
+            ]
+            .into(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 #[serde(default)]
 pub struct Config {
     pub root_dir: PathBuf,
     pub journal: Journal,
+    pub http: Http,
+    pub render: Render,
 }
 
 impl Default for Config {
@@ -48,18 +274,23 @@ impl Default for Config {
         Self {
             root_dir: PathBuf::from("."),
             journal: Journal::default(),
+            http: Http::default(),
+            render: Render::default(),
         }
     }
 }
 
 impl Config {
     pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
-        let s = std::fs::read_to_string(&path)?;
+        let s = std::fs::read_to_string(path)?;
         Self::from_str(&s)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self, Box<dyn Error>> {
-        let config: Config = toml::from_str(&s)?;
+        let config: Config = toml::from_str(s)?;
+        config.journal.validate()?;
+        config.http.auth.validate()?;
         Ok(config)
     }
 
@@ -74,3 +305,120 @@ impl Config {
         Ok(Self::default())
     }
 }
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn http_defaults_to_loopback() {
+        let config = Config::default();
+        assert_eq!("127.0.0.1:10428", config.http.socket_addr().to_string());
+        assert_eq!("", config.http.base_path());
+    }
+
+    #[test]
+    fn http_section() -> Result<(), Box<dyn Error>> {
+        let config = Config::from_str(
+            r#"
+            [http]
+            addr = "0.0.0.0"
+            port = 8080
+            base_path = "notes/"
+        "#,
+        )?;
+
+        assert_eq!("0.0.0.0:8080", config.http.socket_addr().to_string());
+        assert_eq!("/notes", config.http.base_path());
+        assert!(!config.http.auth.is_enabled());
+
+        Ok(())
+    }
+
+    #[test]
+    fn http_auth() -> Result<(), Box<dyn Error>> {
+        let config = Config::from_str(
+            r#"
+            [http]
+            read_only = true
+            exclude = ["private"]
+
+            [http.auth]
+            tokens = ["$argon2id$v=19$m=19456,t=2,p=1$dG9rZW5zYWx0c2FsdA$ok4uIlvuhheIH2vQ51FVQA7sV03Gci087LNlUYfGYwA"]
+
+            [http.auth.users]
+            alice = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHRzYWx0$83NhH4a54XrGQTfUWbmBlx7cfoFGhD1nGBk7kNt4L44"
+        "#,
+        )?;
+
+        assert!(config.http.read_only);
+        assert_eq!(vec!["private"], config.http.exclude);
+        assert!(config.http.auth.is_enabled());
+        assert!(config.http.auth.users.contains_key("alice"));
+        // SHA-256 of "password".
+        let sha256 = "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8";
+        assert!(Config::from_str(&format!("[http.auth]\ntokens = [\"{}\"]", sha256)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn journal_periods() -> Result<(), Box<dyn Error>> {
+        let config = Config::from_str(
+            r#"
+            [journal.periods.sprint]
+            path = "sprints/%Y-%m-%d.md"
+            anchor = "2026-01-05"
+            weeks = 2
+        "#,
+        )?;
+
+        let sprint = &config.journal.periods["sprint"];
+        assert_eq!(
+            chrono::NaiveDate::from_ymd_opt(2026, 1, 5),
+            Some(sprint.anchor)
+        );
+        assert_eq!(14, sprint.length());
+        assert_eq!("journals/%Y-q%q.md", config.journal.path.quarterly);
+        assert_eq!(chrono::Weekday::Sun, config.journal.week_start);
+
+        let config = Config::from_str(
+            r#"
+            [journal]
+            week_start = "monday"
+
+            [journal.path]
+            weekly = "journals/%G-W%V.md"
+        "#,
+        )?;
+        assert_eq!(chrono::Weekday::Mon, config.journal.week_start);
+        assert_eq!(crate::date::Weeks::Iso, config.journal.weeks());
+        // The default weekly path numbers weeks starting on Sunday.
+        assert!(Config::from_str("[journal]\nweek_start = \"monday\"").is_err());
+
+        let period = |name: &str, fields: &str| {
+            Config::from_str(&format!("[journal.periods.{}]\n{}", name, fields))
+                .map_err(|err| err.to_string())
+        };
+        assert!(period("sprint", "path = \"s/%Y.md\"\nweeks = 2")
+            .is_err_and(|err| err.contains("anchor")));
+        assert!(period("sprint", "anchor = \"2026-01-05\"\nweeks = 2")
+            .is_err_and(|err| err.contains("path")));
+        assert!(
+            period("sprint", "path = \"\"\nanchor = \"2026-01-05\"\nweeks = 2")
+                .is_err_and(|err| err == "The period sprint has no path")
+        );
+        assert!(period(
+            "sprint",
+            "path = \"s/%Y.md\"\nanchor = \"2026-01-05\"\nweeks = 0"
+        )
+        .is_err_and(|err| err == "The period sprint must last days or weeks"));
+        assert!(period(
+            "Weekly",
+            "path = \"s/%Y.md\"\nanchor = \"2026-01-05\"\ndays = 7"
+        )
+        .is_err_and(|err| err == "The period Weekly can't be redefined"));
+
+        Ok(())
+    }
+}
@@ -1,12 +1,5 @@
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Context {
     pub config: crate::Config,
-}
-
-impl Default for Context {
-    fn default() -> Self {
-        Self {
-            config: crate::Config::default(),
-        }
-    }
+    pub documents: crate::Documents,
 }
@@ -1,41 +1,245 @@
 use std::error::Error;
-use std::ops::Add;
 
-pub fn parse(date_str: &str) -> Result<chrono::NaiveDate, Box<dyn Error>> {
-    if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-        return Ok(date);
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+/// How the weeks start and are numbered. The configuration derives it from the path of the
+/// weekly journals, so that the numbers of the weeks agree with the names of their journals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weeks {
+    /// Weeks starting on the day, the days of the year before its first one being in the week 0:
+    /// `%U` for Sunday and `%W` for Monday.
+    Starting(Weekday),
+    /// ISO weeks (`%V`), starting on Monday and belonging to the year of their Thursday (`%G`).
+    Iso,
+}
+
+impl Weeks {
+    /// The numbering of the weeks of the weekly path `format`. When it has no week number, ISO
+    /// weeks if they start on Monday, like `%U` otherwise.
+    pub fn new(format: &str, week_start: Weekday) -> Self {
+        if format.contains("%V") {
+            Self::Iso
+        } else if format.contains("%W") {
+            Self::Starting(Weekday::Mon)
+        } else if format.contains("%U") {
+            Self::Starting(Weekday::Sun)
+        } else if week_start == Weekday::Mon {
+            Self::Iso
+        } else {
+            Self::Starting(week_start)
+        }
+    }
+
+    /// First day of the weeks.
+    pub fn start(self) -> Weekday {
+        match self {
+            Self::Starting(weekday) => weekday,
+            Self::Iso => Weekday::Mon,
+        }
+    }
+}
+
+/// Parses a date relative to the current day, see [`parse_from`].
+pub fn parse(date_str: &str, weeks: Weeks) -> Result<NaiveDate, Box<dyn Error>> {
+    parse_from(date_str, chrono::Local::now().date_naive(), weeks)
+}
+
+/// Parses a date, relative to `today` for the expressions that are:
+///
+/// - `2023-10-20`, or `2023-10` and `2023` for the first day of the month or year
+/// - `2023-W42` for the first day of a week, numbered according to `weeks`
+/// - `today`, `yesterday` and `tomorrow`
+/// - `-3d`, `+2w`, `+1m` or `-1y`, also written `3 days ago` or `in 2 weeks`
+/// - `monday`, `next monday` or `last fri`; a weekday alone is the next one, today included
+/// - `start of week`, `end of month` or `end of year`
+pub fn parse_from(
+    date_str: &str,
+    today: NaiveDate,
+    weeks: Weeks,
+) -> Result<NaiveDate, Box<dyn Error>> {
+    let week_start = weeks.start();
+    let input = date_str.trim().to_ascii_lowercase();
+    let words: Vec<&str> = input.split_whitespace().collect();
+
+    let date = match words.as_slice() {
+        ["today"] => Some(today),
+        ["yesterday"] => Some(today - Duration::days(1)),
+        ["tomorrow"] => Some(today + Duration::days(1)),
+        [word] => absolute(word, weeks).or_else(|| {
+            weekday(word)
+                .map(|weekday| next_weekday(today, weekday))
+                .or_else(|| offset(today, word))
+        }),
+        [n, unit, "ago"] => n.parse::<i32>().ok().and_then(|n| shift(today, -n, unit)),
+        ["in", n, unit] => n.parse().ok().and_then(|n| shift(today, n, unit)),
+        ["next", day] => {
+            weekday(day).map(|weekday| next_weekday(today + Duration::days(1), weekday))
+        }
+        ["last", day] => {
+            weekday(day).map(|weekday| last_weekday(today - Duration::days(1), weekday))
+        }
+        ["start", "of", unit] => start_of(today, unit, week_start),
+        ["end", "of", unit] => end_of(today, unit, week_start),
+        _ => None,
+    };
+
+    Ok(date.ok_or_else(|| format!("Invalid date: {}", date_str))?)
+}
+
+/// Dates that don't depend on the current day.
+fn absolute(s: &str, weeks: Weeks) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(date);
     }
 
-    let today = chrono::Local::now().date_naive();
-    if date_str.eq("today") {
-        return Ok(today);
+    if let Some((year, n)) = s.split_once("-w") {
+        return week(year.parse().ok()?, n.parse().ok()?, weeks);
     }
 
-    match date_str {
-        "today" => return Ok(today),
-        "yesterday" => return Ok(today.add(chrono::Duration::days(-1))),
-        "tomorrow" => return Ok(today.add(chrono::Duration::days(1))),
-        _ => {}
+    match s.split_once('-') {
+        Some((year, month)) if month.len() <= 2 => {
+            NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+        }
+        None if s.len() == 4 => NaiveDate::from_ymd_opt(s.parse().ok()?, 1, 1),
+        _ => None,
     }
+}
+
+/// `-3d`, `+2w`, `+1m` or `-1y`.
+fn offset(today: NaiveDate, s: &str) -> Option<NaiveDate> {
+    if !s.starts_with(['+', '-']) {
+        return None;
+    }
+    let split = s.find(|c: char| c.is_ascii_alphabetic())?;
+    let (n, unit) = s.split_at(split);
+    shift(today, n.parse().ok()?, unit)
+}
+
+/// Moves `date` by `n` units, e.g. `days` or `w`.
+fn shift(date: NaiveDate, n: i32, unit: &str) -> Option<NaiveDate> {
+    let months = match unit {
+        "d" | "day" | "days" => return date.checked_add_signed(Duration::days(n.into())),
+        "w" | "week" | "weeks" => return date.checked_add_signed(Duration::weeks(n.into())),
+        "m" | "month" | "months" => n,
+        "y" | "year" | "years" => n.checked_mul(12)?,
+        _ => return None,
+    };
+
+    if months < 0 {
+        date.checked_sub_months(Months::new(months.unsigned_abs()))
+    } else {
+        date.checked_add_months(Months::new(months.unsigned_abs()))
+    }
+}
+
+/// First day of the week `n` of `year`.
+fn week(year: i32, n: u32, weeks: Weeks) -> Option<NaiveDate> {
+    let week_start = match weeks {
+        Weeks::Starting(weekday) => weekday,
+        Weeks::Iso => return NaiveDate::from_isoywd_opt(year, n, Weekday::Mon),
+    };
+
+    // The first week starts on the first `week_start` of the year, the days before are in the
+    // week 0.
+    let new_year = NaiveDate::from_ymd_opt(year, 1, 1)?;
+    let first = next_weekday(new_year, week_start);
+    let start = first.checked_add_signed(Duration::weeks(i64::from(n) - 1))?;
+    let exists = match n {
+        0 => first != new_year,
+        _ => start.year() == year,
+    };
+    exists.then_some(start)
+}
+
+/// Number of the week of `date`, the inverse of the weeks of [`parse_from`].
+pub fn week_number(date: NaiveDate, weeks: Weeks) -> u32 {
+    let week_start = match weeks {
+        Weeks::Starting(weekday) => weekday,
+        Weeks::Iso => return date.iso_week().week(),
+    };
+
+    let start = start_of_week(date, week_start);
+    let new_year = start.with_ordinal(1).unwrap_or(start);
+    let first = next_weekday(new_year, week_start);
+    ((start - first).num_days() / 7 + 1) as u32
+}
 
-    Err(format!("Invalid date: {}", date_str))?
+/// First day of the week of `date`.
+pub fn start_of_week(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    last_weekday(date, week_start)
+}
+
+fn start_of(date: NaiveDate, unit: &str, week_start: Weekday) -> Option<NaiveDate> {
+    match unit {
+        "week" => Some(start_of_week(date, week_start)),
+        "month" => date.with_day(1),
+        "year" => date.with_ordinal(1),
+        _ => None,
+    }
+}
+
+fn end_of(date: NaiveDate, unit: &str, week_start: Weekday) -> Option<NaiveDate> {
+    match unit {
+        "week" => start_of_week(date, week_start).checked_add_signed(Duration::days(6)),
+        "month" => shift(date.with_day(1)?, 1, unit)?.pred_opt(),
+        "year" => NaiveDate::from_ymd_opt(date.year(), 12, 31),
+        _ => None,
+    }
+}
+
+/// English names of the weekdays, whatever the locale, with their abbreviations.
+fn weekday(s: &str) -> Option<Weekday> {
+    const NAMES: [(&str, Weekday); 7] = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+
+    NAMES
+        .iter()
+        .find(|(name, _)| s.len() >= 3 && name.starts_with(s))
+        .map(|(_, weekday)| *weekday)
+}
+
+/// The first `weekday` on or after `date`.
+fn next_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - date.weekday().num_days_from_monday()) % 7;
+    date + Duration::days(days.into())
+}
+
+/// The last `weekday` on or before `date`.
+fn last_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + date.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    date - Duration::days(days.into())
 }
 
 #[cfg(test)]
 mod parse_tests {
     use super::*;
 
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// A Wednesday.
+    fn today() -> NaiveDate {
+        date(2026, 10, 21)
+    }
+
     #[test]
     fn hardcoded_str() -> Result<(), Box<dyn Error>> {
-        let today = chrono::Local::now().date_naive();
-        assert_eq!(today, parse("today").unwrap());
+        assert_eq!(today(), parse_from("today", today(), Weeks::Iso)?);
         assert_eq!(
-            today.add(chrono::Duration::days(-1)),
-            parse("yesterday").unwrap()
+            date(2026, 10, 20),
+            parse_from("yesterday", today(), Weeks::Iso)?
         );
         assert_eq!(
-            today.add(chrono::Duration::days(1)),
-            parse("tomorrow").unwrap()
+            date(2026, 10, 22),
+            parse_from("tomorrow", today(), Weeks::Iso)?
         );
 
         Ok(())
@@ -43,9 +247,106 @@ mod parse_tests {
 
     #[test]
     fn rfc_date() -> Result<(), Box<dyn Error>> {
+        assert_eq!(date(2023, 10, 20), parse("2023-10-20", Weeks::Iso).unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn periods() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            date(2026, 10, 12),
+            parse_from("2026-W42", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2020, 12, 28),
+            parse_from("2020-w53", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 10, 1),
+            parse_from("2026-10", today(), Weeks::Iso)?
+        );
+        assert_eq!(date(2026, 1, 1), parse_from("2026", today(), Weeks::Iso)?);
+        parse_from("2026-W54", today(), Weeks::Iso).expect_err("invalid week");
+        parse_from("2026-13", today(), Weeks::Iso).expect_err("invalid month");
+
+        Ok(())
+    }
+
+    #[test]
+    fn relative() -> Result<(), Box<dyn Error>> {
+        assert_eq!(date(2026, 10, 18), parse_from("-3d", today(), Weeks::Iso)?);
+        assert_eq!(date(2026, 11, 4), parse_from("+2w", today(), Weeks::Iso)?);
+        assert_eq!(date(2026, 11, 21), parse_from("+1m", today(), Weeks::Iso)?);
+        assert_eq!(date(2025, 10, 21), parse_from("-1y", today(), Weeks::Iso)?);
+        assert_eq!(
+            date(2026, 10, 18),
+            parse_from("3 days ago", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 9, 21),
+            parse_from("1 month ago", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 11, 4),
+            parse_from("in 2 weeks", today(), Weeks::Iso)?
+        );
+        parse_from("3d", today(), Weeks::Iso).expect_err("missing sign");
+        parse_from("+3 parsecs", today(), Weeks::Iso).expect_err("invalid unit");
+
+        Ok(())
+    }
+
+    #[test]
+    fn weekdays() -> Result<(), Box<dyn Error>> {
+        assert_eq!(today(), parse_from("Wednesday", today(), Weeks::Iso)?);
+        assert_eq!(date(2026, 10, 23), parse_from("fri", today(), Weeks::Iso)?);
+        assert_eq!(
+            date(2026, 10, 26),
+            parse_from("next monday", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 10, 28),
+            parse_from("next wed", today(), Weeks::Iso)?
+        );
         assert_eq!(
-            chrono::NaiveDate::from_ymd_opt(2023, 10, 20).unwrap(),
-            parse("2023-10-20").unwrap(),
+            date(2026, 10, 16),
+            parse_from("last friday", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 10, 14),
+            parse_from("last wednesday", today(), Weeks::Iso)?
+        );
+        parse_from("next mo", today(), Weeks::Iso).expect_err("ambiguous abbreviation");
+
+        Ok(())
+    }
+
+    #[test]
+    fn boundaries() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            date(2026, 10, 19),
+            parse_from("start of week", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 10, 25),
+            parse_from("end of week", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 10, 1),
+            parse_from("start of month", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 10, 31),
+            parse_from("end of month", today(), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2024, 2, 29),
+            parse_from("end of month", date(2024, 2, 3), Weeks::Iso)?
+        );
+        assert_eq!(
+            date(2026, 12, 31),
+            parse_from("End of Year", today(), Weeks::Iso)?
         );
 
         Ok(())
@@ -53,6 +354,55 @@ mod parse_tests {
 
     #[test]
     fn invalid_date() {
-        parse("abcbc").expect_err("invalid date");
+        parse("abcbc", Weeks::Iso).expect_err("invalid date");
+    }
+
+    #[test]
+    fn week_start() -> Result<(), Box<dyn Error>> {
+        let parse = |s| parse_from(s, today(), Weeks::Starting(Weekday::Sun));
+        assert_eq!(date(2026, 10, 18), parse("start of week")?);
+        assert_eq!(date(2026, 10, 24), parse("end of week")?);
+        // Like `%U`, the first Sunday of 2023 is its first day.
+        assert_eq!(date(2023, 10, 15), parse("2023-w42")?);
+        parse("2023-w00").expect_err("2023 starts on Sunday");
+        assert_eq!(date(2021, 12, 26), parse("2022-w00")?);
+        parse("2022-w53").expect_err("invalid week");
+        assert_eq!(
+            42,
+            week_number(date(2023, 10, 21), Weeks::Starting(Weekday::Sun))
+        );
+        assert_eq!(
+            52,
+            week_number(date(2022, 1, 1), Weeks::Starting(Weekday::Sun))
+        );
+        assert_eq!(53, week_number(date(2021, 1, 3), Weeks::Iso));
+
+        Ok(())
+    }
+
+    #[test]
+    fn numbers_weeks_like_the_format() {
+        let sunday = Weeks::new("journals/%Y-w%U.md", Weekday::Sun);
+        let monday = Weeks::new("journals/%Y-w%W.md", Weekday::Mon);
+        let iso = Weeks::new("journals/%G-W%V.md", Weekday::Mon);
+        assert_eq!(Weeks::Starting(Weekday::Sun), sunday);
+        assert_eq!(Weeks::Starting(Weekday::Mon), monday);
+        assert_eq!(Weeks::Iso, iso);
+        assert_eq!(Weeks::Iso, Weeks::new("weekly.md", Weekday::Mon));
+        assert_eq!(
+            Weeks::Starting(Weekday::Sat),
+            Weeks::new("weekly.md", Weekday::Sat)
+        );
+
+        // Every third day of two years, across the weeks 0 and 53.
+        for day in date(2020, 12, 20).iter_days().take(800).step_by(3) {
+            for (weeks, format) in [(sunday, "%U"), (monday, "%W"), (iso, "%V")] {
+                let start = start_of_week(day, weeks.start());
+                let number: u32 = start.format(format).to_string().parse().unwrap();
+                assert_eq!(number, week_number(day, weeks), "{} {}", day, format);
+            }
+        }
+        assert_eq!(Some(date(2021, 1, 4)), week(2021, 1, monday));
+        assert_eq!(Some(date(2020, 12, 28)), week(2020, 53, iso));
     }
 }
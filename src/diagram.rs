@@ -0,0 +1,119 @@
+// Rendering of diagram code blocks, e.g. mermaid or graphviz, by the local commands of the
+// configuration. The source of a block is written to the standard input of the command, which
+// writes the SVG to its standard output. Rendering blocks until the command exits, so the server
+// renders notes on the blocking thread pool.
+
+use std::io::{Read, Write};
+use std::num::NonZeroUsize;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+/// How long a command may take before the source block is shown instead.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many diagrams are kept in the cache.
+const CACHE_SIZE: usize = 256;
+
+/// SVG of the diagrams rendered last, keyed by the hash of the command and the source, so that
+/// the live preview does not run the commands again for every change.
+static CACHE: OnceLock<Mutex<LruCache<String, String>>> = OnceLock::new();
+
+/// Renders `source` with `command`. Returns `None` if the command cannot be run, fails or
+/// times out.
+pub(crate) fn render(command: &[String], source: &str) -> Option<String> {
+    let key = crate::auth::sha256_hex(format!("{:?}\0{}", command, source).as_bytes());
+    let cache =
+        CACHE.get_or_init(|| Mutex::new(LruCache::new(NonZeroUsize::new(CACHE_SIZE).unwrap())));
+    if let Some(svg) = cache.lock().ok()?.get(&key) {
+        return Some(svg.clone());
+    }
+
+    let svg = run(command, source)?;
+    cache.lock().ok()?.put(key, svg.clone());
+    Some(svg)
+}
+
+fn run(command: &[String], source: &str) -> Option<String> {
+    let (program, args) = command.split_first()?;
+    let mut child = match Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            log::warn!("{}: Unable to run {}", err, program);
+            return None;
+        }
+    };
+
+    // Write and read from other threads so that large diagrams cannot fill the pipes and block.
+    let mut stdin = child.stdin.take()?;
+    let source = source.to_string();
+    std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    let mut stdout = child.stdout.take()?;
+    let output = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            _ => {
+                log::warn!("{} did not render the diagram in time", program);
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+
+    let output = output.join().ok()?.ok()?;
+    if !status.success() {
+        log::warn!("{} failed to render the diagram: {}", program, status);
+        return None;
+    }
+
+    // Drop anything before the SVG, e.g. an XML declaration.
+    let start = output.find("<svg")?;
+    Some(output[start..].trim_end().to_string())
+}
+
+#[cfg(test)]
+mod diagram_tests {
+    use super::*;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn renders_with_command() {
+        let svg = render(
+            &command(&["sh", "-c", "echo '<?xml?>'; cat"]),
+            "<svg>a</svg>\n",
+        );
+        assert_eq!(Some(String::from("<svg>a</svg>")), svg);
+    }
+
+    #[test]
+    fn falls_back() {
+        assert_eq!(
+            None,
+            render(&command(&["sh", "-c", "cat; exit 1"]), "<svg/>")
+        );
+        assert_eq!(None, render(&command(&["cat"]), "graph { a -- b }"));
+        assert_eq!(None, render(&command(&["/nonexistent/command"]), "<svg/>"));
+        assert_eq!(None, render(&[], "<svg/>"));
+    }
+}
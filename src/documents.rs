@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+use tokio::sync::RwLock;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The unsaved text of the document changed.
+    Changed(PathBuf),
+    /// The cursor of the editor moved to a zero based line of the document.
+    CursorMoved(PathBuf, u32),
+}
+
+/// Text of the documents opened in an editor, keyed by their canonical path. Shared between the
+/// LSP and HTTP servers so that the preview follows unsaved changes.
+#[derive(Clone, Debug)]
+pub struct Documents {
+    texts: Arc<RwLock<HashMap<PathBuf, String>>>,
+    events: broadcast::Sender<Event>,
+}
+
+impl Default for Documents {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            texts: Arc::new(RwLock::new(HashMap::new())),
+            events,
+        }
+    }
+}
+
+impl Documents {
+    pub async fn replace(&self, path: &Path, text: String) {
+        let path = key(path);
+        self.texts.write().await.insert(path.clone(), text);
+        let _ = self.events.send(Event::Changed(path));
+    }
+
+    pub async fn remove(&self, path: &Path) {
+        let path = key(path);
+        self.texts.write().await.remove(&path);
+        let _ = self.events.send(Event::Changed(path));
+    }
+
+    pub async fn text(&self, path: &Path) -> Option<String> {
+        self.texts.read().await.get(&key(path)).cloned()
+    }
+
+    pub fn move_cursor(&self, path: &Path, line: u32) {
+        let _ = self.events.send(Event::CursorMoved(key(path), line));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+}
+
+fn key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or(path.to_path_buf())
+}
+
+#[cfg(test)]
+mod documents_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn notifies_changes() -> crate::Result<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        let documents = Documents::default();
+        let mut events = documents.subscribe();
+
+        documents.replace(file.path(), String::from("text")).await;
+        assert_eq!(
+            Some(String::from("text")),
+            documents.text(file.path()).await
+        );
+        assert_eq!(
+            Event::Changed(file.path().canonicalize()?),
+            events.recv().await?
+        );
+
+        documents.move_cursor(file.path(), 3);
+        assert_eq!(
+            Event::CursorMoved(file.path().canonicalize()?, 3),
+            events.recv().await?
+        );
+
+        documents.remove(file.path()).await;
+        assert_eq!(None, documents.text(file.path()).await);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,353 @@
+// Static site export of the notes. Pages are rendered like the ones of the HTTP server, with the
+// links rewritten to relative `.html` files so that the site can be browsed from any directory,
+// even without a server.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::html::{escape, site_path, site_url, LinkStyle, Links};
+
+/// Notes to export. Every note the HTTP server would serve is exported when empty.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Tags the notes must all have.
+    pub tags: Vec<String>,
+    /// Values of the front matter the notes must all have, e.g. `("publish", "true")`.
+    pub front_matter: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub notes: usize,
+    pub assets: usize,
+}
+
+impl Filter {
+    fn matches(&self, note: &crate::Note) -> bool {
+        let tags = note.tags();
+        self.tags.iter().all(|tag| tags.contains(tag))
+            && self.front_matter.iter().all(|(key, expected)| {
+                note.front_matter
+                    .get(key)
+                    .is_some_and(|value| value_matches(value, expected))
+            })
+    }
+}
+
+fn value_matches(value: &serde_json::Value, expected: &str) -> bool {
+    match value {
+        serde_json::Value::String(value) => value == expected,
+        serde_json::Value::Array(values) => values.iter().any(|v| value_matches(v, expected)),
+        // Booleans and numbers, e.g. `publish=true`.
+        value => {
+            let value = value.to_string();
+            value == expected
+        }
+    }
+}
+
+/// Renders the notes matching `filter` as HTML pages under `out_dir`, along with the files they
+/// link to, an index of the notes and a page for each tag.
+pub fn html(config: &crate::Config, out_dir: &Path, filter: &Filter) -> crate::Result<Summary> {
+    let root_dir = &config.root_dir;
+    let mut all_notes = crate::http::visible_notes(config);
+    all_notes.retain(|note| filter.matches(note));
+    let mut notes: Vec<(String, &crate::Note)> = all_notes
+        .iter()
+        .map(|note| (crate::http::relative_path(root_dir, &note.path), note))
+        .collect();
+    notes.sort_by(|a, b| a.0.cmp(&b.0));
+    let paths: BTreeSet<String> = notes.iter().map(|(path, _)| path.clone()).collect();
+
+    let mut summary = Summary::default();
+    let mut assets = BTreeSet::new();
+    for (path, note) in &notes {
+        let content = std::fs::read_to_string(&note.path)?;
+        let links = Links::new(root_dir, Path::new(path), LinkStyle::Site { notes: &paths });
+        let body = crate::html::render(&content, &links, &config.render)?;
+
+        let mut page = crate::theme::Page {
+            title: note.title.clone(),
+            toc: crate::html::toc(&body),
+            body,
+            front_matter: note.front_matter.clone(),
+            breadcrumbs: breadcrumbs(path, &[(&note.title, path)]),
+            ..Default::default()
+        };
+        (page.backlinks, page.links) = crate::html::link_lists(&links, note, all_notes.iter());
+
+        write(config, out_dir, &site_path(path), &page)?;
+        summary.notes += 1;
+        assets.extend(links.targets.into_inner());
+    }
+
+    for asset in assets.iter().filter(|asset| !asset.ends_with(".md")) {
+        let Some(filepath) = crate::http::resolve_path(config, asset) else {
+            continue;
+        };
+        if filepath.is_file() {
+            copy(&filepath, &out_dir.join(asset))?;
+            summary.assets += 1;
+        }
+    }
+
+    // A note at the root named `index.md` stands for the home page.
+    if !paths.contains("index.md") {
+        write(config, out_dir, "index.html", &index(&notes))?;
+    }
+    write_tags(config, out_dir, &notes)?;
+    write_theme(config, out_dir)?;
+
+    Ok(summary)
+}
+
+/// The list of the exported notes.
+fn index(notes: &[(String, &crate::Note)]) -> crate::theme::Page {
+    let mut body = String::from("<h1>Notes</h1>\n<ul class=\"notes\">\n");
+    for (path, note) in notes {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{}</small></li>\n",
+            escape(&site_url("index.html", path)),
+            escape(&note.title),
+            escape(path),
+        ));
+    }
+    body.push_str("</ul>\n<p><a href=\"tags/index.html\">Tags</a></p>\n");
+
+    crate::theme::Page {
+        title: String::from("Notes"),
+        body,
+        breadcrumbs: breadcrumbs("index.html", &[]),
+        ..Default::default()
+    }
+}
+
+/// Writes `tags/index.html` listing the tags, and a page listing the notes of each tag.
+fn write_tags(
+    config: &crate::Config,
+    out_dir: &Path,
+    notes: &[(String, &crate::Note)],
+) -> crate::Result<()> {
+    let mut tags: BTreeMap<String, Vec<(&str, &crate::Note)>> = BTreeMap::new();
+    for (path, note) in notes {
+        for tag in note.tags() {
+            tags.entry(tag).or_default().push((path, note));
+        }
+    }
+
+    let paths = tag_paths(tags.keys());
+    let mut body = String::from("<h1>Tags</h1>\n<ul class=\"tags\">\n");
+    for (tag, tagged) in &tags {
+        let page_path = &paths[tag.as_str()];
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{}</small></li>\n",
+            escape(&site_url("tags/index.html", page_path)),
+            escape(tag),
+            tagged.len(),
+        ));
+
+        let mut tag_body = format!("<h1>{}</h1>\n<ul class=\"notes\">\n", escape(tag));
+        for (path, note) in tagged {
+            tag_body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                escape(&site_url(page_path, path)),
+                escape(&note.title),
+            ));
+        }
+        tag_body.push_str("</ul>\n");
+
+        let page = crate::theme::Page {
+            title: tag.clone(),
+            body: tag_body,
+            breadcrumbs: breadcrumbs(page_path, &[("Tags", "tags/index.html"), (tag, page_path)]),
+            ..Default::default()
+        };
+        write(config, out_dir, page_path, &page)?;
+    }
+    body.push_str("</ul>\n");
+
+    let page = crate::theme::Page {
+        title: String::from("Tags"),
+        body,
+        breadcrumbs: breadcrumbs("tags/index.html", &[("Tags", "tags/index.html")]),
+        ..Default::default()
+    };
+    write(config, out_dir, "tags/index.html", &page)
+}
+
+/// Copies the bundled stylesheets and the files of the theme, except its layout.
+fn write_theme(config: &crate::Config, out_dir: &Path) -> crate::Result<()> {
+    for (name, content_type, content) in crate::html::BUNDLED_ASSETS {
+        if *content_type == "text/css" {
+            let path = out_dir.join("_assets").join(name);
+            std::fs::create_dir_all(out_dir.join("_assets"))?;
+            std::fs::write(path, content)?;
+        }
+    }
+
+    let theme_dir = config.root_dir.join(crate::theme::THEME_DIR);
+    for entry in walkdir::WalkDir::new(&theme_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+    {
+        let path = entry.path().strip_prefix(&theme_dir)?;
+        if path != Path::new(crate::theme::LAYOUT) {
+            copy(entry.path(), &out_dir.join("_theme").join(path))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Paths of the pages of `tags`, relative to the root of the site. Tags having the same slug,
+/// e.g. `C` and `C++`, are told apart by a number.
+fn tag_paths<'a>(tags: impl Iterator<Item = &'a String>) -> BTreeMap<&'a str, String> {
+    let mut used = BTreeSet::from([String::from("tags/index.html")]);
+    let mut paths = BTreeMap::new();
+    for tag in tags {
+        let mut slug = crate::html::slug(tag);
+        if slug.is_empty() {
+            slug = String::from("_");
+        }
+        let mut path = format!("tags/{}.html", slug);
+        for n in 2.. {
+            if used.insert(path.clone()) {
+                break;
+            }
+            path = format!("tags/{}-{}.html", slug, n);
+        }
+        paths.insert(tag.as_str(), path);
+    }
+    paths
+}
+
+/// Links to the index followed by `crumbs`, pairs of names and paths, relative to `page`.
+fn breadcrumbs(page: &str, crumbs: &[(&str, &str)]) -> Vec<crate::theme::Breadcrumb> {
+    std::iter::once(("Notes", "index.html"))
+        .chain(crumbs.iter().copied())
+        .map(|(name, path)| crate::theme::Breadcrumb {
+            name: String::from(name),
+            url: site_url(page, path),
+        })
+        .collect()
+}
+
+fn write(
+    config: &crate::Config,
+    out_dir: &Path,
+    path: &str,
+    page: &crate::theme::Page,
+) -> crate::Result<()> {
+    let filepath = out_dir.join(path);
+    if let Some(parent) = filepath.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let html = crate::theme::render_site(config, page, &site_url(path, ""));
+    std::fs::write(filepath, html)?;
+    Ok(())
+}
+
+fn copy(from: &Path, to: &Path) -> crate::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(from, to)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::*;
+
+    fn vault() -> crate::Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("guides/images"))?;
+        std::fs::create_dir_all(dir.path().join("private"))?;
+        std::fs::write(
+            dir.path().join("guides/setup.md"),
+            "---\npublish: true\ntags: [onboarding]\n---\n# Setup\n\n\
+             ![Screen](images/screen.png) See [FAQ](../faq.md) and [secret](../private/todo.md).\n",
+        )?;
+        std::fs::write(dir.path().join("guides/images/screen.png"), "png")?;
+        std::fs::write(
+            dir.path().join("faq.md"),
+            "---\npublish: true\n---\n# FAQ\n\nRead [setup](guides/setup.md#install).\n",
+        )?;
+        std::fs::write(dir.path().join("private/todo.md"), "# Todo\n")?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn exports_published_notes() -> crate::Result<()> {
+        let dir = vault()?;
+        let out = tempfile::tempdir()?;
+        let config = crate::Config {
+            root_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let filter = Filter {
+            front_matter: vec![(String::from("publish"), String::from("true"))],
+            ..Default::default()
+        };
+
+        let summary = html(&config, out.path(), &filter)?;
+        assert_eq!(
+            Summary {
+                notes: 2,
+                assets: 1
+            },
+            summary
+        );
+        assert!(!out.path().join("private/todo.html").exists());
+        assert!(out.path().join("guides/images/screen.png").exists());
+        assert!(out.path().join("_assets/page.css").exists());
+
+        let setup = std::fs::read_to_string(out.path().join("guides/setup.html"))?;
+        assert!(setup.contains(r#"<img src="images/screen.png""#));
+        assert!(setup.contains(r#"<a href="../faq.html">FAQ</a>"#));
+        assert!(setup.contains(r#"<a href="../private/todo.html" class="broken-link">"#));
+        assert!(setup
+            .contains(r#"<li><a href="../private/todo.html" class="broken-link">secret</a></li>"#));
+        assert!(setup.contains(r#"<li><a href="../faq.html">FAQ</a></li>"#));
+        assert!(setup.contains(r#"href="../_assets/page.css""#));
+        assert!(!setup.contains("/search"));
+
+        let faq = std::fs::read_to_string(out.path().join("faq.html"))?;
+        assert!(faq.contains(r#"<a href="guides/setup.html#install">"#));
+        assert!(faq.contains(r#"href="./_assets/page.css""#));
+
+        let index = std::fs::read_to_string(out.path().join("index.html"))?;
+        assert!(index.contains(r#"<a href="faq.html">FAQ</a>"#));
+        let tag = std::fs::read_to_string(out.path().join("tags/onboarding.html"))?;
+        assert!(tag.contains(r#"<a href="../guides/setup.html">Setup</a>"#));
+        Ok(())
+    }
+
+    #[test]
+    fn tells_tags_apart() {
+        let tags = ["C", "C++", "index", "c-2", "?"].map(String::from);
+        let paths = tag_paths(tags.iter());
+        assert_eq!("tags/c.html", paths["C"]);
+        assert_eq!("tags/c-2.html", paths["C++"]);
+        assert_eq!("tags/index-2.html", paths["index"]);
+        assert_eq!("tags/c-2-2.html", paths["c-2"]);
+        assert_eq!("tags/_.html", paths["?"]);
+    }
+
+    #[test]
+    fn filters() -> crate::Result<()> {
+        let dir = vault()?;
+        let notes = crate::Note::all(dir.path());
+        let note = |name: &str| notes.iter().find(|n| n.path.ends_with(name)).unwrap();
+
+        let by_tag = Filter {
+            tags: vec![String::from("onboarding")],
+            ..Default::default()
+        };
+        assert!(by_tag.matches(note("setup.md")));
+        assert!(!by_tag.matches(note("faq.md")));
+        assert!(Filter::default().matches(note("todo.md")));
+        Ok(())
+    }
+}
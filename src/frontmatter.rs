@@ -0,0 +1,69 @@
+// Front matter of the notes, either TOML between `+++` or YAML between `---`.
+
+use serde_json::{Map, Value};
+
+pub type FrontMatter = Map<String, Value>;
+
+pub fn parse_toml(s: &str) -> Result<FrontMatter, Box<dyn std::error::Error>> {
+    let table: toml::Table = toml::from_str(s)?;
+    match serde_json::to_value(table)? {
+        Value::Object(map) => Ok(map),
+        _ => Ok(Map::new()),
+    }
+}
+
+pub fn parse_yaml(s: &str) -> Result<FrontMatter, Box<dyn std::error::Error>> {
+    match serde_yaml::from_str(s)? {
+        Value::Object(map) => Ok(map),
+        _ => Ok(Map::new()),
+    }
+}
+
+#[cfg(test)]
+mod frontmatter_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn yaml() -> crate::Result<()> {
+        let front_matter = parse_yaml(
+            r#"title: "Weekly: review"
+publish: true
+priority: 2
+tags: [work, 'planning']
+aliases:
+  - review
+  - retro
+date: 2023-10-21 # comment
+author:
+  name: Someone
+"#,
+        )?;
+
+        assert_eq!(
+            json!({
+                "title": "Weekly: review",
+                "publish": true,
+                "priority": 2,
+                "tags": ["work", "planning"],
+                "aliases": ["review", "retro"],
+                "date": "2023-10-21",
+                "author": {"name": "Someone"},
+            }),
+            Value::Object(front_matter)
+        );
+        assert!(parse_yaml("")?.is_empty());
+        assert!(parse_yaml("tags: [work").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn toml() -> crate::Result<()> {
+        let front_matter = parse_toml("title = \"Note\"\ntags = [\"a\"]\n")?;
+        assert_eq!(
+            json!({"title": "Note", "tags": ["a"]}),
+            Value::Object(front_matter)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,430 @@
+// A small lexer based syntax highlighter so that code blocks are highlighted without any
+// client side script. It only distinguishes keywords, strings, numbers, comments, function calls
+// and type names, which is enough for reading notes.
+
+struct Syntax {
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+}
+
+static SYNTAXES: &[Syntax] = &[
+    Syntax {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+    },
+    Syntax {
+        names: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+            "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return",
+            "True", "try", "while", "with", "yield",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+    },
+    Syntax {
+        names: &["javascript", "js", "typescript", "ts", "jsx", "tsx"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "of",
+            "return",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+    },
+    Syntax {
+        names: &["sh", "bash", "shell", "zsh", "console"],
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "select", "then", "until", "while",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+    },
+    Syntax {
+        names: &["c", "h", "cpp", "c++", "cc", "hpp"],
+        keywords: &[
+            "auto",
+            "break",
+            "case",
+            "char",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "double",
+            "else",
+            "enum",
+            "extern",
+            "false",
+            "float",
+            "for",
+            "if",
+            "inline",
+            "int",
+            "long",
+            "namespace",
+            "new",
+            "nullptr",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "short",
+            "signed",
+            "sizeof",
+            "static",
+            "struct",
+            "switch",
+            "template",
+            "this",
+            "true",
+            "typedef",
+            "union",
+            "unsigned",
+            "using",
+            "virtual",
+            "void",
+            "volatile",
+            "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+    },
+    Syntax {
+        names: &["go", "golang"],
+        keywords: &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "fallthrough",
+            "false",
+            "for",
+            "func",
+            "go",
+            "goto",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+    },
+    Syntax {
+        names: &["java", "kotlin", "kt"],
+        keywords: &[
+            "abstract",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "continue",
+            "default",
+            "do",
+            "else",
+            "extends",
+            "false",
+            "final",
+            "finally",
+            "for",
+            "fun",
+            "if",
+            "implements",
+            "import",
+            "instanceof",
+            "interface",
+            "new",
+            "null",
+            "package",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "throws",
+            "true",
+            "try",
+            "val",
+            "var",
+            "void",
+            "when",
+            "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+    },
+    Syntax {
+        names: &["lua"],
+        keywords: &[
+            "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in",
+            "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+        ],
+        line_comments: &["--"],
+        block_comment: None,
+        quotes: &['"', '\''],
+    },
+    Syntax {
+        names: &["vim", "viml"],
+        keywords: &[
+            "call",
+            "else",
+            "elseif",
+            "endfor",
+            "endfunction",
+            "endif",
+            "endwhile",
+            "execute",
+            "for",
+            "function",
+            "if",
+            "in",
+            "let",
+            "return",
+            "set",
+            "unlet",
+            "while",
+        ],
+        line_comments: &["\""],
+        block_comment: None,
+        quotes: &['\''],
+    },
+    Syntax {
+        names: &["sql"],
+        keywords: &[
+            "and", "as", "by", "create", "delete", "from", "group", "having", "insert", "into",
+            "join", "left", "limit", "not", "null", "on", "or", "order", "select", "set", "table",
+            "update", "values", "where", "AND", "AS", "BY", "CREATE", "DELETE", "FROM", "GROUP",
+            "HAVING", "INSERT", "INTO", "JOIN", "LEFT", "LIMIT", "NOT", "NULL", "ON", "OR",
+            "ORDER", "SELECT", "SET", "TABLE", "UPDATE", "VALUES", "WHERE",
+        ],
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\''],
+    },
+    Syntax {
+        names: &["toml", "ini", "yaml", "yml"],
+        keywords: &["true", "false", "null"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+    },
+    Syntax {
+        names: &["json"],
+        keywords: &["true", "false", "null"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+    },
+];
+
+fn syntax(lang: &str) -> Option<&'static Syntax> {
+    let lang = lang.to_lowercase();
+    SYNTAXES
+        .iter()
+        .find(|syntax| syntax.names.contains(&lang.as_str()))
+}
+
+/// Highlights `code` written in `lang` and returns the escaped HTML, or `None` if the language is
+/// unknown.
+pub fn highlight(lang: &str, code: &str) -> Option<String> {
+    let syntax = syntax(lang)?;
+
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut rest = code;
+    while let Some(c) = rest.chars().next() {
+        let (class, len) = if let Some(len) = comment_len(syntax, rest) {
+            (Some("comment"), len)
+        } else if syntax.quotes.contains(&c) {
+            (Some("string"), string_len(rest, c))
+        } else if c.is_ascii_digit() {
+            (Some("number"), ident_len(rest))
+        } else if c.is_alphabetic() || c == '_' {
+            let len = ident_len(rest);
+            let word = &rest[..len];
+            let class = if syntax.keywords.contains(&word) {
+                Some("keyword")
+            } else if rest[len..].starts_with('(') {
+                Some("function")
+            } else if c.is_uppercase() && word.chars().any(|c| c.is_lowercase()) {
+                Some("type")
+            } else {
+                None
+            };
+            (class, len)
+        } else {
+            (None, c.len_utf8())
+        };
+
+        let token = crate::html::escape(&rest[..len]);
+        match class {
+            Some(class) => {
+                html.push_str(&format!(r#"<span class="hl-{}">{}</span>"#, class, token))
+            }
+            None => html.push_str(&token),
+        }
+        rest = &rest[len..];
+    }
+
+    Some(html)
+}
+
+fn comment_len(syntax: &Syntax, s: &str) -> Option<usize> {
+    if let Some((start, end)) = syntax.block_comment {
+        if let Some(body) = s.strip_prefix(start) {
+            return Some(match body.find(end) {
+                Some(pos) => start.len() + pos + end.len(),
+                None => s.len(),
+            });
+        }
+    }
+
+    if syntax
+        .line_comments
+        .iter()
+        .any(|prefix| s.starts_with(prefix))
+    {
+        return Some(s.find('\n').unwrap_or(s.len()));
+    }
+
+    None
+}
+
+fn string_len(s: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        } else if c == '\n' && quote != '`' && quote != '"' {
+            return i;
+        }
+    }
+
+    s.len()
+}
+
+fn ident_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(s.len())
+}
+
+#[cfg(test)]
+mod highlight_tests {
+    use super::*;
+
+    #[test]
+    fn unknown_language() {
+        assert_eq!(None, highlight("brainfuck", "+++"));
+    }
+
+    #[test]
+    fn rust() {
+        assert_eq!(
+            Some(String::from(
+                r#"<span class="hl-keyword">let</span> x = <span class="hl-function">f</span>(<span class="hl-string">&quot;a &lt; b&quot;</span>, <span class="hl-number">42</span>); <span class="hl-comment">// &lt;span&gt;</span>"#
+            )),
+            highlight("Rust", r#"let x = f("a < b", 42); // <span>"#),
+        );
+    }
+
+    #[test]
+    fn python() {
+        assert_eq!(
+            Some(String::from(
+                r#"<span class="hl-keyword">def</span> <span class="hl-function">f</span>(): <span class="hl-keyword">return</span> <span class="hl-string">'it\'s'</span>"#
+            )),
+            highlight("py", r#"def f(): return 'it\'s'"#),
+        );
+    }
+
+    #[test]
+    fn block_comment() {
+        assert_eq!(
+            Some(String::from(
+                "<span class=\"hl-comment\">/* a\nb */</span> <span class=\"hl-type\">Foo</span>"
+            )),
+            highlight("c", "/* a\nb */ Foo"),
+        );
+    }
+}
@@ -1,25 +1,63 @@
 use axum::{
-    extract::{Path, State},
-    http::header,
+    extract::{Path, Query, State},
+    http::HeaderMap,
     http::StatusCode,
-    response::Html,
     response::{IntoResponse, Response},
 };
+use percent_encoding::utf8_percent_encode;
 
 pub async fn serve_page(
     State(state): State<crate::http::ServerState>,
     Path(path): Path<String>,
+    Query(params): Query<crate::listing::Params>,
+    headers: HeaderMap,
 ) -> Response {
-    let filepath = state.config.root_dir.join(&path);
-    if !filepath.exists() || !filepath.is_file() {
-        return (StatusCode::NOT_FOUND, "Failed to found related files").into_response();
+    let filepath = match crate::http::resolve_path(&state.config, &path) {
+        Some(filepath) => filepath,
+        None => return (StatusCode::NOT_FOUND, "Failed to found related files").into_response(),
+    };
+
+    if filepath.is_dir() {
+        return crate::listing::serve_directory(&state, &path, &params);
     }
 
     if filepath.extension().is_some_and(|ext| ext != "md") {
-        return serve_asset(&filepath).await;
+        return crate::asset::serve_file(&filepath, &headers).await;
+    }
+
+    serve_note(&state, &path, &filepath, &headers).await
+}
+
+/// Renders the configured home note, or lists the root directory if there is none.
+pub async fn serve_home(
+    State(state): State<crate::http::ServerState>,
+    Query(params): Query<crate::listing::Params>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(home_note) = &state.config.http.home_note else {
+        return crate::listing::serve_directory(&state, "", &params);
+    };
+
+    match crate::http::resolve_path(&state.config, home_note) {
+        Some(filepath) if filepath.is_file() => {
+            serve_note(&state, home_note, &filepath, &headers).await
+        }
+        _ => (StatusCode::NOT_FOUND, "Failed to found the home note").into_response(),
     }
+}
 
-    let content = match std::fs::read_to_string(&filepath) {
+async fn serve_note(
+    state: &crate::http::ServerState,
+    path: &str,
+    filepath: &std::path::Path,
+    headers: &HeaderMap,
+) -> Response {
+    // Prefer the unsaved text of the editor, if any.
+    let content = match state.documents.text(filepath).await {
+        Some(content) => Ok(content),
+        None => std::fs::read_to_string(filepath),
+    };
+    let content = match content {
         Ok(content) => content,
         Err(err) => {
             log::error!("{}: Unable to open file {}", err, filepath.display());
@@ -31,7 +69,19 @@ pub async fn serve_page(
         }
     };
 
-    let html = match markdown::to_html_with_options(&content, &markdown::Options::gfm()) {
+    let base_path = state.config.http.base_path();
+    // Diagrams are rendered by commands that may take a while.
+    let rendered = {
+        let config = state.config.clone();
+        let (path, base_path, content) = (String::from(path), base_path.clone(), content.clone());
+        tokio::task::spawn_blocking(move || {
+            let links = server_links(&config.root_dir, &path, &base_path);
+            render(&content, &links, &config.render)
+        })
+        .await
+        .unwrap_or_else(|err| Err(err.to_string()))
+    };
+    let html = match rendered {
         Ok(html) => html,
         Err(err) => {
             log::error!(
@@ -47,76 +97,714 @@ pub async fn serve_page(
         }
     };
 
-    // TODO: make the stylesheet costomizable
-    let html = String::from(
-        r##"<head>
-          <link href="https://cdn.jsdelivr.net/npm/bootstrap@5.3.2/dist/css/bootstrap.min.css" rel="stylesheet" integrity="sha384-T3c6CoIi6uLrA9TneNEoa7RxnatzjcDSCmG1MXxSR1GAsXEV/Dwwykc2MPK8M2HN" crossorigin="anonymous">
-          <link rel="stylesheet" href="https://esm.sh/@wooorm/starry-night@3/style/both.css">
-          <script src="https://cdn.jsdelivr.net/npm/bootstrap@5.3.2/dist/js/bootstrap.bundle.min.js" integrity="sha384-C6RzsynM9kWDrMNeT87bh95OGNyZPhcTNXj1NW7RuBCsyN/o0jlpcV8Qyq46cDfL" crossorigin="anonymous"></script>
-          <style>
-            p img {
-              border-radius: 8px;
-              max-width: 100%;
-              height: auto;
+    let mut page = crate::theme::Page {
+        title: String::from(path),
+        toc: toc(&html),
+        body: html,
+        breadcrumbs: breadcrumbs(&base_path, path),
+        source_lines: crate::preview::source_lines(&content),
+        events_url: Some(route_url(&base_path, "_events", path)),
+        ..Default::default()
+    };
+
+    // The parse error isn't `Send`, so it mustn't live across the await below.
+    let note = crate::Note::build_from_str(&state.config.root_dir.join(path), &content).ok();
+    if let Some(note) = note {
+        page.title = note.title.clone();
+        page.front_matter = note.front_matter.clone();
+        let root_dir = state.config.root_dir.clone();
+        let (path, base_path) = (String::from(path), base_path.clone());
+        (page.backlinks, page.links) = crate::http::with_visible_notes(state, move |notes| {
+            let links = server_links(&root_dir, &path, &base_path);
+            link_lists(&links, &note, notes.iter().copied())
+        })
+        .await;
+    }
+    if let Some(crumb) = page.breadcrumbs.last_mut() {
+        crumb.name = page.title.clone();
+    }
+    // Finding the journals around lists the journal directories.
+    let ctx = crate::Context {
+        config: state.config.clone(),
+        documents: state.documents.clone(),
+    };
+    let (filepath, journal_base_path) = (filepath.to_path_buf(), base_path.clone());
+    page.journal =
+        tokio::task::spawn_blocking(move || journal_links(&ctx, &filepath, &journal_base_path))
+            .await
+            .unwrap_or_default();
+
+    crate::asset::serve_html(headers, crate::theme::render(&state.config, &page))
+}
+
+/// Links of the note at `path`, pointing to the pages of the server.
+fn server_links<'a>(root_dir: &'a std::path::Path, path: &'a str, base_path: &'a str) -> Links<'a> {
+    Links::new(
+        root_dir,
+        std::path::Path::new(path),
+        LinkStyle::Server { base_path },
+    )
+}
+
+/// Links to the journals around the one at `filepath`, if it's a journal.
+fn journal_links(
+    ctx: &crate::Context,
+    filepath: &std::path::Path,
+    base_path: &str,
+) -> Vec<crate::theme::JournalLink> {
+    let previous = crate::journal::previous(ctx, filepath);
+    let next = crate::journal::next(ctx, filepath);
+    // Unlike the editor, which can create it, pages only link to a parent that exists.
+    let parent = crate::journal::parent(ctx, filepath).filter(|parent| parent.exists());
+
+    let related = [("previous", previous), ("next", next), ("parent", parent)]
+        .into_iter()
+        .filter_map(|(rel, path)| Some((rel, path?)))
+        .chain(
+            crate::journal::children(ctx, filepath)
+                .into_iter()
+                .map(|path| ("child", path)),
+        );
+
+    related
+        .map(|(rel, path)| {
+            let title = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let path = crate::http::relative_path(&ctx.config.root_dir, &path);
+            crate::theme::JournalLink {
+                rel,
+                title,
+                url: page_url(base_path, &path),
             }
-          </style>
-        </head>
-        <body>
-        <div class="container-xxl px-4 px-xxl-2">
-          <div class="content mx-auto my-5" style="max-width: 760px">"##,
-    ) + html.as_str()
-        + r##"
-          </div>
-        </div>
-        <script type="module">
-          import {
-            common,
-            createStarryNight
-          } from 'https://esm.sh/@wooorm/starry-night@3?bundle'
-          import {toDom} from 'https://esm.sh/hast-util-to-dom@4?bundle'
-
-          const starryNight = await createStarryNight(common)
-          const prefix = 'language-'
-
-          const nodes = Array.from(document.body.querySelectorAll('code'))
-
-          for (const node of nodes) {
-            const className = Array.from(node.classList).find(function (d) {
-              return d.startsWith(prefix)
-            })
-            if (!className) continue
-            const scope = starryNight.flagToScope(className.slice(prefix.length))
-            if (!scope) continue
-            const tree = starryNight.highlight(node.textContent, scope)
-            node.replaceChildren(toDom(tree, {fragment: true}))
-          }
-        </script>
-        </body>
-    "##;
-    Html(html).into_response()
-}
-
-async fn serve_asset(filepath: &std::path::Path) -> Response {
-    let content_type = match mime_guess::from_path(&filepath).first_raw() {
-        Some(mime) => mime,
-        None => {
-            return (StatusCode::BAD_REQUEST, "MIME Type couldn't be determined").into_response()
+        })
+        .collect()
+}
+
+/// The notes linking to `note` with the text around the links, and the links of `note`.
+pub(crate) fn link_lists<'a>(
+    links: &Links,
+    note: &crate::Note,
+    notes: impl Iterator<Item = &'a crate::Note> + Clone,
+) -> (Vec<crate::theme::PageLink>, Vec<crate::theme::PageLink>) {
+    let root_dir = crate::note::normalize(links.root_dir);
+    let relative = |path: &std::path::Path| {
+        let path = crate::note::normalize(path);
+        let path = path.strip_prefix(&root_dir).unwrap_or(&path);
+        path.to_string_lossy().into_owned()
+    };
+    let url = |path: &std::path::Path| links.url(&relative(path));
+
+    let backlinks = note
+        .backlinks(notes.clone(), links.root_dir)
+        .into_iter()
+        .map(|(source, link)| crate::theme::PageLink {
+            title: source.title.clone(),
+            url: url(&source.path),
+            snippet: snippet(&link.context, 160),
+            broken: false,
+        })
+        .collect();
+
+    let links = note
+        .links()
+        .iter()
+        .map(|link| {
+            let (href, title, broken) = match link.target(links.root_dir) {
+                Some(target) => {
+                    let title = notes
+                        .clone()
+                        .find(|note| crate::note::normalize(&note.path) == target)
+                        .map(|note| note.title.clone());
+                    let broken = !links.exists(&relative(&target));
+                    (url(&target), title.or(link.title.clone()), broken)
+                }
+                None => (link.url.clone(), link.title.clone(), false),
+            };
+            crate::theme::PageLink {
+                title: title.unwrap_or(link.url.clone()),
+                url: href,
+                snippet: snippet(&link.context, 160),
+                broken,
+            }
+        })
+        .collect();
+
+    (backlinks, links)
+}
+
+/// Links to the root directory and each parent directory of `path`, ending with `path` itself.
+pub(crate) fn breadcrumbs(base_path: &str, path: &str) -> Vec<crate::theme::Breadcrumb> {
+    let mut breadcrumbs = vec![crate::theme::Breadcrumb {
+        name: String::from("Notes"),
+        url: page_url(base_path, ""),
+    }];
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    for i in 0..segments.len() {
+        let mut url = page_url(base_path, &segments[..=i].join("/"));
+        if i + 1 < segments.len() {
+            url.push('/');
         }
+        breadcrumbs.push(crate::theme::Breadcrumb {
+            name: String::from(segments[i]),
+            url,
+        });
+    }
+
+    breadcrumbs
+}
+
+/// Shortens `text` to about `max_chars` characters on a word boundary.
+fn snippet(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    let mut snippet: String = text.chars().take(max_chars).collect();
+    if let Some(pos) = snippet.rfind(' ') {
+        snippet.truncate(pos);
+    }
+    snippet.push('…');
+    snippet
+}
+
+/// Serves the stylesheets compiled into the binary so that pages render without network access.
+pub async fn serve_bundled_asset(Path(path): Path<String>, headers: HeaderMap) -> Response {
+    match BUNDLED_ASSETS.iter().find(|(name, _, _)| *name == path) {
+        Some((_, content_type, content)) => {
+            crate::asset::serve_generated(&headers, content_type, content.to_string())
+        }
+        None => (StatusCode::NOT_FOUND, "Asset not found").into_response(),
+    }
+}
+
+pub(crate) static BUNDLED_ASSETS: &[(&str, &str, &str)] = &[
+    ("page.css", "text/css", include_str!("../assets/page.css")),
+    (
+        "highlight.css",
+        "text/css",
+        include_str!("../assets/highlight.css"),
+    ),
+    (
+        "preview.js",
+        "text/javascript",
+        include_str!("../assets/preview.js"),
+    ),
+];
+
+/// Where a rendered note lives, used to resolve the links inside it.
+pub(crate) struct Links<'a> {
+    pub root_dir: &'a std::path::Path,
+    /// Path of the note relative to `root_dir`.
+    pub note_path: &'a std::path::Path,
+    pub style: LinkStyle<'a>,
+    /// Existing files the links point to, relative to `root_dir`. Filled while rendering.
+    pub targets: std::cell::RefCell<std::collections::BTreeSet<String>>,
+}
+
+/// How the links to other files of the root directory are written.
+pub(crate) enum LinkStyle<'a> {
+    /// Routes of the HTTP server.
+    Server { base_path: &'a str },
+    /// Relative links between the files of a static site, where notes become `.html` pages.
+    /// Links to notes missing from `notes` are broken.
+    Site {
+        notes: &'a std::collections::BTreeSet<String>,
+    },
+}
+
+impl<'a> Links<'a> {
+    pub fn new(
+        root_dir: &'a std::path::Path,
+        note_path: &'a std::path::Path,
+        style: LinkStyle<'a>,
+    ) -> Self {
+        Self {
+            root_dir,
+            note_path,
+            style,
+            targets: Default::default(),
+        }
+    }
+
+    /// URL of `path`, a path relative to `root_dir` separated by slashes.
+    pub fn url(&self, path: &str) -> String {
+        match self.style {
+            LinkStyle::Server { base_path } => page_url(base_path, path),
+            LinkStyle::Site { .. } => site_url(&self.note_path.to_string_lossy(), path),
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        match self.style {
+            LinkStyle::Site { notes } if path.ends_with(".md") => notes.contains(path),
+            _ => self.root_dir.join(path).exists(),
+        }
+    }
+}
+
+/// Relative URL of `path` from the page of a static site at `page`, both relative to the root of
+/// the site.
+pub(crate) fn site_url(page: &str, path: &str) -> String {
+    let mut dirs: Vec<&str> = page.split('/').filter(|s| !s.is_empty()).collect();
+    dirs.pop();
+    let path = site_path(path);
+    let target: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    // Skip the directories the page and the target have in common.
+    let common = dirs
+        .iter()
+        .zip(target.iter().take(target.len().saturating_sub(1)))
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut segments = vec![String::from(".."); dirs.len() - common];
+    segments.extend(
+        target[common..]
+            .iter()
+            .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string()),
+    );
+    if segments.is_empty() {
+        return String::from(".");
+    }
+    segments.join("/")
+}
+
+/// Path of the file of a static site holding `path`, with notes rendered as `.html` pages.
+pub(crate) fn site_path(path: &str) -> String {
+    match path.strip_suffix(".md") {
+        Some(stem) => format!("{}.html", stem),
+        None => String::from(path),
+    }
+}
+
+/// Converts markdown to the HTML body of a page, with math and diagrams rendered, code blocks
+/// highlighted, anchors on the headings and internal links pointing to the server routes.
+pub(crate) fn render(
+    content: &str,
+    links: &Links,
+    options: &crate::config::Render,
+) -> Result<String, String> {
+    let markdown_options = markdown::Options {
+        parse: crate::note::render_options(),
+        compile: markdown::CompileOptions::gfm(),
     };
+    let html = markdown::to_html_with_options(content, &markdown_options)?;
+    let html = render_math(&html);
+    let html = render_diagrams(&html, options);
+    let html = highlight_code_blocks(&html);
+    let html = anchor_headings(&html);
+    Ok(rewrite_links(&html, links))
+}
+
+fn render_math(html: &str) -> String {
+    static MATH: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let math = MATH.get_or_init(|| {
+        regex::Regex::new(
+            r#"(?s)<pre><code class="language-math(?: math-display)?">(.*?)</code></pre>|<code class="language-math math-inline">(.*?)</code>"#,
+        )
+        .unwrap()
+    });
+
+    math.replace_all(html, |caps: &regex::Captures| match caps.get(1) {
+        Some(tex) => crate::math::to_mathml(&unescape(tex.as_str()), true),
+        None => crate::math::to_mathml(&unescape(&caps[2]), false),
+    })
+    .into_owned()
+}
 
-    let file = match tokio::fs::File::open(filepath).await {
-        Ok(file) => file,
-        Err(_) => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+/// Replaces the code blocks of the languages having a command with the SVG it renders, keeping
+/// the block if the command fails.
+fn render_diagrams(html: &str, options: &crate::config::Render) -> String {
+    static CODE_BLOCK: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let code_block = CODE_BLOCK.get_or_init(|| {
+        regex::Regex::new(r#"(?s)<pre><code class="language-([^"]+)">(.*?)</code></pre>"#).unwrap()
+    });
+
+    code_block
+        .replace_all(html, |caps: &regex::Captures| {
+            options
+                .diagrams
+                .get(&caps[1])
+                .and_then(|command| crate::diagram::render(command, &unescape(&caps[2])))
+                .map(|svg| format!(r#"<figure class="diagram">{}</figure>"#, svg))
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+fn highlight_code_blocks(html: &str) -> String {
+    static CODE_BLOCK: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let code_block = CODE_BLOCK.get_or_init(|| {
+        regex::Regex::new(r#"(?s)<pre><code class="language-([^"]+)">(.*?)</code></pre>"#).unwrap()
+    });
+
+    code_block
+        .replace_all(html, |caps: &regex::Captures| {
+            let lang = &caps[1];
+            match crate::highlight::highlight(lang, &unescape(&caps[2])) {
+                Some(code) => format!(
+                    r#"<pre><code class="language-{}">{}</code></pre>"#,
+                    lang, code
+                ),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+fn heading_regex() -> &'static regex::Regex {
+    static HEADING: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    HEADING.get_or_init(|| regex::Regex::new(r#"<h([1-6])( id="[^"]*")?>(.*?)</h[1-6]>"#).unwrap())
+}
+
+/// Adds an id to every heading so that they can be linked to, e.g. from the table of contents.
+fn anchor_headings(html: &str) -> String {
+    let mut ids: Vec<String> = Vec::new();
+    heading_regex()
+        .replace_all(html, |caps: &regex::Captures| {
+            let text = strip_tags(&caps[3]);
+            let mut id = slug(&text);
+            let count = ids.iter().filter(|other| **other == id).count();
+            ids.push(id.clone());
+            if count > 0 {
+                id = format!("{}-{}", id, count);
+            }
+            format!(
+                r#"<h{} id="{}">{}</h{}>"#,
+                &caps[1],
+                escape(&id),
+                &caps[3],
+                &caps[1]
+            )
+        })
+        .into_owned()
+}
+
+/// Headings of rendered HTML, in order.
+pub(crate) fn toc(html: &str) -> Vec<crate::theme::Heading> {
+    heading_regex()
+        .captures_iter(html)
+        .filter_map(|caps| {
+            let id = caps.get(2)?.as_str();
+            Some(crate::theme::Heading {
+                depth: caps[1].parse().ok()?,
+                id: unescape(&id[5..id.len() - 1]),
+                text: strip_tags(&caps[3]),
+            })
+        })
+        .collect()
+}
+
+fn strip_tags(html: &str) -> String {
+    static TAG: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let tag = TAG.get_or_init(|| regex::Regex::new(r"<[^>]*>").unwrap());
+    unescape(&tag.replace_all(html, ""))
+}
+
+/// GitHub style anchor of a heading.
+pub(crate) fn slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn rewrite_links(html: &str, links: &Links) -> String {
+    static LINK: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let link = LINK.get_or_init(|| regex::Regex::new(r#"<(a href|img src)="([^"]*)""#).unwrap());
+
+    link.replace_all(html, |caps: &regex::Captures| {
+        let Some((url, exists)) = resolve_link(&unescape(&caps[2]), links) else {
+            return caps[0].to_string();
+        };
+
+        let class = if exists {
+            ""
+        } else {
+            r#" class="broken-link""#
+        };
+        format!(r#"<{}="{}"{}"#, &caps[1], escape(&url), class)
+    })
+    .into_owned()
+}
+
+/// Resolves an internal link against the location of the note. Returns the URL of the target on
+/// the server and whether the target exists, or `None` for external links.
+fn resolve_link(url: &str, links: &Links) -> Option<(String, bool)> {
+    static EXTERNAL: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let external =
+        EXTERNAL.get_or_init(|| regex::Regex::new(r"^([a-zA-Z][a-zA-Z0-9+.-]*:|//)").unwrap());
+    if external.is_match(url) {
+        return None;
+    }
+
+    let (path, suffix) = match url.find(['#', '?']) {
+        Some(pos) => url.split_at(pos),
+        None => (url, ""),
     };
+    if path.is_empty() {
+        return None;
+    }
+    let path = percent_encoding::percent_decode_str(path).decode_utf8_lossy();
 
-    let stream = tokio_util::io::ReaderStream::new(file);
-    let body = axum::body::StreamBody::new(stream);
+    let mut segments: Vec<String> = Vec::new();
+    if !path.starts_with('/') {
+        for component in links.note_path.parent().into_iter().flat_map(|p| p.iter()) {
+            segments.push(component.to_string_lossy().into_owned());
+        }
+    }
 
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-        header::CONTENT_TYPE,
-        header::HeaderValue::from_str(content_type).unwrap(),
-    );
+    let mut exists = true;
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => exists &= segments.pop().is_some(),
+            segment => segments.push(String::from(segment)),
+        }
+    }
+
+    let target = segments.join("/");
+    exists &= links.exists(&target);
+    if exists {
+        links.targets.borrow_mut().insert(target.clone());
+    }
+
+    let mut url = links.url(&target);
+    if path.ends_with('/') && !target.is_empty() {
+        url.push('/');
+    }
+    url.push_str(suffix);
+
+    Some((url, exists))
+}
+
+/// URL of the page serving `path`, a path relative to `root_dir` separated by slashes.
+pub(crate) fn page_url(base_path: &str, path: &str) -> String {
+    route_url(base_path, "pages", path)
+}
+
+pub(crate) fn route_url(base_path: &str, route: &str, path: &str) -> String {
+    let path: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+        .collect();
+
+    format!("{}/{}/{}", base_path, route, path.join("/"))
+}
+
+const PATH_SEGMENT: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+pub(crate) fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod render_tests {
+    use super::*;
+
+    fn vault() -> crate::Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("journals"))?;
+        std::fs::create_dir_all(dir.path().join("projects/my project"))?;
+        std::fs::write(dir.path().join("projects/x.md"), "# X")?;
+        std::fs::write(dir.path().join("projects/my project/y.md"), "# Y")?;
+        Ok(dir)
+    }
+
+    fn render_link(dir: &tempfile::TempDir, link: &str) -> String {
+        let links = Links::new(
+            dir.path(),
+            std::path::Path::new("journals/2023-10-21.md"),
+            LinkStyle::Server {
+                base_path: "/notes",
+            },
+        );
+        render(link, &links, &Default::default()).unwrap()
+    }
+
+    #[test]
+    fn relative_links() -> crate::Result<()> {
+        let dir = vault()?;
+        assert_eq!(
+            "<p><a href=\"/notes/pages/projects/x.md#goals\">x</a></p>",
+            render_link(&dir, "[x](../projects/x.md#goals)"),
+        );
+        assert_eq!(
+            "<p><a href=\"/notes/pages/projects/my%20project/y.md\">y</a></p>",
+            render_link(&dir, "[y](<../projects/my project/y.md>)"),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn absolute_links() -> crate::Result<()> {
+        let dir = vault()?;
+        assert_eq!(
+            "<p><a href=\"/notes/pages/projects/x.md\">x</a></p>",
+            render_link(&dir, "[x](/projects/x.md)"),
+        );
+        assert_eq!(
+            "<p><a href=\"/notes/pages/projects/\">p</a></p>",
+            render_link(&dir, "[p](/projects/)"),
+        );
+        Ok(())
+    }
 
-    (headers, body).into_response()
+    #[test]
+    fn broken_links() -> crate::Result<()> {
+        let dir = vault()?;
+        assert_eq!(
+            "<p><a href=\"/notes/pages/journals/missing.md\" class=\"broken-link\">m</a></p>",
+            render_link(&dir, "[m](missing.md)"),
+        );
+        assert_eq!(
+            "<p><a href=\"/notes/pages/projects/x.md\" class=\"broken-link\">x</a></p>",
+            render_link(&dir, "[x](../../../projects/x.md)"),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn headings() -> crate::Result<()> {
+        let dir = vault()?;
+        let html = render_link(&dir, "# Title\n\n## Goals & *plans*\n\n## Goals & plans");
+        assert_eq!(
+            "<h1 id=\"title\">Title</h1>\n<h2 id=\"goals--plans\">Goals &amp; <em>plans</em></h2>\n<h2 id=\"goals--plans-1\">Goals &amp; plans</h2>",
+            html
+        );
+        assert_eq!(
+            vec![
+                (1, "title", "Title"),
+                (2, "goals--plans", "Goals & plans"),
+                (2, "goals--plans-1", "Goals & plans"),
+            ],
+            toc(&html)
+                .iter()
+                .map(|h| (h.depth, h.id.as_str(), h.text.as_str()))
+                .collect::<Vec<(u8, &str, &str)>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn front_matter_is_not_rendered() -> crate::Result<()> {
+        let dir = vault()?;
+        assert_eq!("<p>Body</p>", render_link(&dir, "---\ntitle: T\n---\nBody"));
+        Ok(())
+    }
+
+    #[test]
+    fn breadcrumbs_of_a_note() {
+        assert_eq!(
+            vec![
+                ("Notes", "/n/pages/"),
+                ("projects", "/n/pages/projects/"),
+                ("x.md", "/n/pages/projects/x.md"),
+            ],
+            breadcrumbs("/n", "projects/x.md")
+                .iter()
+                .map(|b| (b.name.as_str(), b.url.as_str()))
+                .collect::<Vec<(&str, &str)>>()
+        );
+    }
+
+    #[test]
+    fn snippets() {
+        assert_eq!("a b c", snippet("a\n b   c", 10));
+        assert_eq!("one two…", snippet("one two three", 10));
+    }
+
+    #[test]
+    fn external_links() -> crate::Result<()> {
+        let dir = vault()?;
+        for link in [
+            "[a](https://example.com/a.md)",
+            "[a](mailto:a@example.com)",
+            "[a](#heading)",
+        ] {
+            assert!(
+                !render_link(&dir, link).contains("/notes/pages"),
+                "{}",
+                link
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn math() -> crate::Result<()> {
+        let dir = vault()?;
+        let html = render_link(&dir, "Inline $x^2$ and\n\n$$\n\\frac{a}{b}\n$$\n");
+        assert!(html.contains(
+            r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="inline"><semantics><msup><mi>x</mi><mn>2</mn></msup>"#
+        ));
+        assert!(html.contains(r#"display="block"><semantics><mfrac><mi>a</mi><mi>b</mi></mfrac>"#));
+        assert!(!html.contains("language-math"));
+        Ok(())
+    }
+
+    #[test]
+    fn diagrams() -> crate::Result<()> {
+        let dir = vault()?;
+        let links = Links::new(
+            dir.path(),
+            std::path::Path::new("a.md"),
+            LinkStyle::Server { base_path: "" },
+        );
+        let options = crate::config::Render {
+            diagrams: [
+                (String::from("svg"), vec![String::from("cat")]),
+                (String::from("broken"), vec![String::from("false")]),
+            ]
+            .into(),
+        };
+
+        let html = render("```svg\n<svg>&</svg>\n```\n", &links, &options)?;
+        assert_eq!("<figure class=\"diagram\"><svg>&</svg></figure>\n", html);
+
+        let html = render("```broken\na -> b\n```\n", &links, &options)?;
+        assert_eq!(
+            "<pre><code class=\"language-broken\">a -&gt; b\n</code></pre>\n",
+            html
+        );
+        Ok(())
+    }
 }
@@ -1,22 +1,611 @@
-use axum::routing::get;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use axum::middleware;
+use axum::routing::{get, post};
+use tower_http::compression::predicate::{DefaultPredicate, Predicate};
 
 #[derive(Clone)]
 pub struct ServerState {
     pub config: crate::Config,
+    pub documents: crate::Documents,
+    pub index: crate::search::SharedIndex,
+    /// Held while the API modifies a note, so that requests don't interleave their checks and
+    /// writes.
+    pub writing: std::sync::Arc<tokio::sync::Mutex<()>>,
+    /// SHA-256 of the authorization headers already verified against the slow hashes.
+    pub authorized: std::sync::Arc<std::sync::Mutex<std::collections::HashSet<String>>>,
 }
 
 pub async fn serve(cx: crate::Context) {
-    log::info!("HTTP server is listening 10428");
-    let http_server =
-        axum::Server::bind(&"0.0.0.0:10428".parse().unwrap()).serve(router(cx).into_make_service());
+    let addr = cx.config.http.socket_addr();
+    let http_server = match axum::Server::try_bind(&addr) {
+        Ok(builder) => builder.serve(router(cx).into_make_service()),
+        Err(err) => {
+            log::error!("{}: Unable to bind HTTP server to {}", err, addr);
+            return;
+        }
+    };
 
+    log::info!("HTTP server is listening {}", addr);
     http_server.await.unwrap();
 }
 
-fn router(cx: crate::Context) -> axum::Router {
-    axum::Router::new()
+pub(crate) fn router(cx: crate::Context) -> axum::Router {
+    let base_path = cx.config.http.base_path();
+    let state = ServerState {
+        config: cx.config,
+        documents: cx.documents,
+        index: Default::default(),
+        writing: Default::default(),
+        authorized: Default::default(),
+    };
+    let router = axum::Router::new()
         // TODO The members of ServerState are be cloned every time. Refactor the members to make
         // them as pointers.
+        .route("/", get(crate::html::serve_home))
+        .route("/pages/", get(crate::listing::serve_root))
         .route("/pages/*path", get(crate::html::serve_page))
-        .with_state(ServerState { config: cx.config })
+        .route("/search", get(crate::search::serve_search))
+        .route("/journal", get(crate::calendar::serve_calendar))
+        .route("/api/search", get(crate::search::serve_api_search))
+        .route("/api/notes", get(crate::api::list_notes))
+        .route(
+            "/api/notes/*path",
+            get(crate::api::get_note)
+                .put(crate::api::put_note)
+                .delete(crate::api::delete_note),
+        )
+        .route("/api/tags", get(crate::api::list_tags))
+        .route("/api/journal/:period/:date", get(crate::api::get_journal))
+        .route("/_assets/*path", get(crate::html::serve_bundled_asset))
+        .route("/_theme/*path", get(crate::theme::serve_theme_asset))
+        .route("/_events/*path", get(crate::preview::serve_events))
+        .route("/_cursor/*path", post(crate::preview::move_cursor))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::auth::reject_writes,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::auth::authenticate,
+        ))
+        .layer(
+            tower_http::compression::CompressionLayer::new()
+                .no_deflate()
+                .compress_when(DefaultPredicate::new().and(crate::asset::compressible)),
+        )
+        .with_state(state);
+
+    if base_path.is_empty() {
+        return router;
+    }
+
+    axum::Router::new().nest(base_path.as_str(), router)
+}
+
+/// Maps a requested path onto a file under `root_dir`. Returns `None` whenever the path escapes
+/// the root directory or points to something the server must not expose.
+pub(crate) fn resolve_path(config: &crate::Config, path: &str) -> Option<PathBuf> {
+    resolve_path_in(&config.root_dir, config, path)
+}
+
+/// Same as [`resolve_path`] but for a path under `root_dir` instead of the one configured.
+pub(crate) fn resolve_path_in(
+    root_dir: &Path,
+    config: &crate::Config,
+    path: &str,
+) -> Option<PathBuf> {
+    let root_dir = root_dir.canonicalize().ok()?;
+
+    if is_excluded(config, Path::new(path)) {
+        return None;
+    }
+
+    let mut filepath = root_dir.clone();
+    for component in Path::new(path).components() {
+        let name = match component {
+            Component::Normal(name) => name.to_str()?,
+            Component::CurDir => continue,
+            _ => return None,
+        };
+
+        if is_hidden(config, name) {
+            return None;
+        }
+
+        filepath.push(name);
+        if config.http.symlinks == crate::Symlinks::Deny && filepath.is_symlink() {
+            return None;
+        }
+    }
+
+    let filepath = filepath.canonicalize().ok()?;
+    if config.http.symlinks != crate::Symlinks::Follow && !filepath.starts_with(&root_dir) {
+        return None;
+    }
+    // A symbolic link may lead to a hidden file, to .git or to an excluded directory.
+    if let Ok(resolved) = filepath.strip_prefix(&root_dir) {
+        let hidden = resolved.components().any(|component| match component {
+            Component::Normal(name) => is_hidden(config, &name.to_string_lossy()),
+            _ => false,
+        });
+        if hidden || is_excluded(config, resolved) {
+            return None;
+        }
+    }
+
+    Some(filepath)
+}
+
+/// Same as [`resolve_path`] but for a file that may not exist yet, along with the directories
+/// leading to it.
+pub(crate) fn resolve_new_path(config: &crate::Config, path: &str) -> Option<PathBuf> {
+    if let Some(filepath) = resolve_path(config, path) {
+        return Some(filepath);
+    }
+
+    let path = Path::new(path.trim_start_matches('/'));
+    if is_excluded(config, path) {
+        return None;
+    }
+    let mut missing = Vec::new();
+    let mut existing = path;
+    while !config.root_dir.join(existing).exists() {
+        let name = existing.file_name()?.to_str()?;
+        if is_hidden(config, name) {
+            return None;
+        }
+        missing.push(name);
+        existing = existing.parent()?;
+    }
+
+    let mut filepath = resolve_path(config, existing.to_str()?)?;
+    filepath.extend(missing.iter().rev());
+    Some(filepath)
+}
+
+/// Whether `path`, relative to the root directory, is under one of the excluded directories.
+fn is_excluded(config: &crate::Config, path: &Path) -> bool {
+    let names = |path: &Path| -> Vec<String> {
+        path.components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let path = names(path);
+    config
+        .http
+        .exclude
+        .iter()
+        .map(|dir| names(Path::new(dir)))
+        .any(|dir| !dir.is_empty() && path.starts_with(&dir))
+}
+
+/// The notes the server is allowed to serve.
+pub(crate) fn visible_notes(config: &crate::Config) -> Vec<crate::Note> {
+    let mut index = crate::search::Index::default();
+    index.update(&config.root_dir, |path| is_walked(config, path));
+    index
+        .into_notes()
+        .filter(|note| is_visible(config, note))
+        .collect()
+}
+
+/// Whether walks of the notes should go into `path`, relative to the root directory: it's neither
+/// hidden nor excluded. Its parent directories are assumed to have been checked.
+pub(crate) fn is_walked(config: &crate::Config, path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .is_some_and(|name| is_hidden(config, &name.to_string_lossy()));
+    !hidden && !is_excluded(config, path)
+}
+
+/// Runs `f` on the notes the server is allowed to serve, from the index it keeps.
+pub(crate) async fn with_visible_notes<T, F>(state: &ServerState, f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce(&[&crate::Note]) -> T + Send + 'static,
+{
+    let config = state.config.clone();
+    state
+        .index
+        .with(&state.config, move |index| {
+            let notes: Vec<&crate::Note> = index
+                .notes()
+                .filter(|note| is_visible(&config, note))
+                .collect();
+            f(&notes)
+        })
+        .await
+}
+
+/// Whether the server is allowed to serve `note`.
+pub(crate) fn is_visible(config: &crate::Config, note: &crate::Note) -> bool {
+    resolve_path(config, &relative_path(&config.root_dir, &note.path)).is_some()
+}
+
+/// Path of `path` relative to `root_dir`, separated by slashes.
+pub(crate) fn relative_path(root_dir: &Path, path: &Path) -> String {
+    let root_dir = crate::note::normalize(root_dir);
+    let path = crate::note::normalize(path);
+    let path = match path.strip_prefix(&root_dir) {
+        Ok(path) => path.to_path_buf(),
+        // Resolved paths are canonical while the root directory may not be.
+        Err(_) => match root_dir.canonicalize() {
+            Ok(root_dir) => path.strip_prefix(root_dir).unwrap_or(&path).to_path_buf(),
+            Err(_) => path,
+        },
+    };
+
+    path.iter()
+        .map(|name| name.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn is_hidden(config: &crate::Config, name: &str) -> bool {
+    name == ".git" || (name.starts_with('.') && !config.http.serve_hidden)
+}
+
+#[cfg(test)]
+mod router_tests {
+    use super::*;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    struct Vault {
+        dir: tempfile::TempDir,
+        cx: crate::Context,
+    }
+
+    fn vault() -> crate::Result<Vault> {
+        let dir = tempfile::tempdir()?;
+        let root_dir = dir.path().join("notes");
+        std::fs::create_dir_all(root_dir.join("sub"))?;
+        std::fs::create_dir_all(root_dir.join(".git"))?;
+        std::fs::create_dir_all(dir.path().join("outside"))?;
+
+        std::fs::write(root_dir.join("sub/note.md"), "# Note")?;
+        std::fs::write(root_dir.join(".hidden.md"), "# Hidden")?;
+        std::fs::write(root_dir.join(".git/config"), "[core]")?;
+        std::fs::write(dir.path().join("outside/secret.md"), "# Secret")?;
+        std::os::unix::fs::symlink(dir.path().join("outside"), root_dir.join("outside"))?;
+        std::os::unix::fs::symlink(root_dir.join("sub"), root_dir.join("inside"))?;
+
+        let mut cx = crate::Context::default();
+        cx.config.root_dir = root_dir;
+        Ok(Vault { dir, cx })
+    }
+
+    async fn status(cx: &crate::Context, uri: &str) -> StatusCode {
+        let request = Request::builder().uri(uri).body(axum::body::Body::empty());
+        send(cx, request.unwrap()).await
+    }
+
+    async fn send(cx: &crate::Context, request: Request<axum::body::Body>) -> StatusCode {
+        router(cx.clone()).oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn serves_notes() -> crate::Result<()> {
+        let vault = vault()?;
+        assert_eq!(
+            StatusCode::OK,
+            status(&vault.cx, "/pages/sub/note.md").await
+        );
+        assert_eq!(
+            StatusCode::OK,
+            status(&vault.cx, "/pages/./sub/note.md").await
+        );
+        assert_eq!(
+            StatusCode::OK,
+            status(&vault.cx, "/pages/inside/note.md").await
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serves_directories() -> crate::Result<()> {
+        let mut vault = vault()?;
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/").await);
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/pages/").await);
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/pages/sub").await);
+        assert_eq!(
+            StatusCode::OK,
+            status(&vault.cx, "/pages/sub/?sort=modified&order=desc").await
+        );
+
+        vault.cx.config.http.home_note = Some(String::from("sub/note.md"));
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/").await);
+        vault.cx.config.http.home_note = Some(String::from("../outside/secret.md"));
+        assert_eq!(StatusCode::NOT_FOUND, status(&vault.cx, "/").await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn hides_excluded_backlinks() -> crate::Result<()> {
+        let mut vault = vault()?;
+        vault.cx.config.http.exclude = vec![String::from("private")];
+        let root_dir = &vault.cx.config.root_dir;
+        std::fs::create_dir_all(root_dir.join("private"))?;
+        std::fs::create_dir_all(root_dir.join(".trash"))?;
+        let link = "# Secret plans\n\nSee [the note](/sub/note.md).\n";
+        std::fs::write(root_dir.join("private/plans.md"), link)?;
+        std::fs::write(root_dir.join(".trash/old.md"), link)?;
+        std::fs::write(
+            root_dir.join("public.md"),
+            "# Public\n\n[Note](sub/note.md)\n",
+        )?;
+
+        let request = Request::builder()
+            .uri("/pages/sub/note.md")
+            .body(axum::body::Body::empty())?;
+        let response = router(vault.cx.clone()).oneshot(request).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("/pages/public.md"));
+        assert!(!body.contains("plans"));
+        assert!(!body.contains("Secret"));
+        assert!(!body.contains("old.md"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serves_bundled_assets() -> crate::Result<()> {
+        let vault = vault()?;
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/_assets/page.css").await);
+        assert_eq!(
+            StatusCode::NOT_FOUND,
+            status(&vault.cx, "/_assets/none.css").await
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_traversal() -> crate::Result<()> {
+        let vault = vault()?;
+        for uri in [
+            "/pages/../outside/secret.md",
+            "/pages/sub/../../outside/secret.md",
+            "/pages/..%2Foutside%2Fsecret.md",
+            "/pages/%2e%2e/outside/secret.md",
+            "/pages/sub%2F%2E%2E%2F%2E%2E%2Foutside%2Fsecret.md",
+            "/pages//etc/passwd",
+            "/pages/%2Fetc%2Fpasswd",
+        ] {
+            assert_eq!(
+                StatusCode::NOT_FOUND,
+                status(&vault.cx, uri).await,
+                "{}",
+                uri
+            );
+        }
+        assert!(vault.dir.path().join("outside/secret.md").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_hidden_files() -> crate::Result<()> {
+        let mut vault = vault()?;
+        assert_eq!(
+            StatusCode::NOT_FOUND,
+            status(&vault.cx, "/pages/.hidden.md").await
+        );
+        assert_eq!(
+            StatusCode::NOT_FOUND,
+            status(&vault.cx, "/pages/.git/config").await
+        );
+
+        vault.cx.config.http.serve_hidden = true;
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/pages/.hidden.md").await);
+        assert_eq!(
+            StatusCode::NOT_FOUND,
+            status(&vault.cx, "/pages/.git/config").await
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_links_to_hidden_files() -> crate::Result<()> {
+        let vault = vault()?;
+        let root_dir = &vault.cx.config.root_dir;
+        std::os::unix::fs::symlink(root_dir.join(".git"), root_dir.join("gitlink"))?;
+        std::os::unix::fs::symlink(root_dir.join(".hidden.md"), root_dir.join("visible.md"))?;
+
+        for uri in [
+            "/pages/gitlink/config",
+            "/pages/gitlink/",
+            "/pages/visible.md",
+        ] {
+            assert_eq!(
+                StatusCode::NOT_FOUND,
+                status(&vault.cx, uri).await,
+                "{}",
+                uri
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn symlink_policy() -> crate::Result<()> {
+        let mut vault = vault()?;
+        assert_eq!(
+            StatusCode::NOT_FOUND,
+            status(&vault.cx, "/pages/outside/secret.md").await
+        );
+
+        vault.cx.config.http.symlinks = crate::Symlinks::Deny;
+        assert_eq!(
+            StatusCode::NOT_FOUND,
+            status(&vault.cx, "/pages/inside/note.md").await
+        );
+
+        vault.cx.config.http.symlinks = crate::Symlinks::Follow;
+        assert_eq!(
+            StatusCode::OK,
+            status(&vault.cx, "/pages/outside/secret.md").await
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn excludes_directories() -> crate::Result<()> {
+        let mut vault = vault()?;
+        vault.cx.config.http.exclude = vec![String::from("sub/")];
+        for uri in [
+            "/pages/sub/note.md",
+            "/pages/./sub/note.md",
+            "/pages/inside/note.md",
+            "/api/notes/sub/note.md",
+        ] {
+            assert_eq!(
+                StatusCode::NOT_FOUND,
+                status(&vault.cx, uri).await,
+                "{}",
+                uri
+            );
+        }
+        assert!(resolve_new_path(&vault.cx.config, "sub/new.md").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn authenticates() -> crate::Result<()> {
+        let mut vault = vault()?;
+        // The Argon2 hash of "test".
+        vault.cx.config.http.auth.tokens = vec![String::from(
+            "$argon2id$v=19$m=8,t=1,p=1$dG9rZW5zYWx0c2FsdA$GHs8ZuYSmubytq+AfbSpYjyX4NEMUm9XckOFGwR1cRU",
+        )];
+
+        assert_eq!(
+            StatusCode::UNAUTHORIZED,
+            status(&vault.cx, "/pages/sub/note.md").await
+        );
+        for (authorization, expected) in [
+            ("Bearer test", StatusCode::OK),
+            ("Bearer wrong", StatusCode::UNAUTHORIZED),
+        ] {
+            let request = Request::builder()
+                .uri("/pages/sub/note.md")
+                .header("authorization", authorization)
+                .body(axum::body::Body::empty())?;
+            assert_eq!(expected, send(&vault.cx, request).await);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_only() -> crate::Result<()> {
+        let mut vault = vault()?;
+        vault.cx.config.http.read_only = true;
+
+        let request = Request::builder()
+            .method("DELETE")
+            .uri("/api/notes/sub/note.md")
+            .body(axum::body::Body::empty())?;
+        assert_eq!(StatusCode::FORBIDDEN, send(&vault.cx, request).await);
+        assert!(vault.cx.config.root_dir.join("sub/note.md").exists());
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/_cursor/sub/note.md?line=1")
+            .body(axum::body::Body::empty())?;
+        assert_ne!(StatusCode::FORBIDDEN, send(&vault.cx, request).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn caches_and_compresses() -> crate::Result<()> {
+        let vault = vault()?;
+        std::fs::write(vault.cx.config.root_dir.join("sub/data.bin"), "0123456789")?;
+
+        let request = Request::builder()
+            .uri("/_assets/page.css")
+            .header("Accept-Encoding", "gzip")
+            .body(axum::body::Body::empty())?;
+        let response = router(vault.cx.clone()).oneshot(request).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("gzip", response.headers()["Content-Encoding"]);
+        let etag = response.headers()["ETag"].clone();
+
+        let request = Request::builder()
+            .uri("/_assets/page.css")
+            .header("If-None-Match", etag)
+            .body(axum::body::Body::empty())?;
+        assert_eq!(StatusCode::NOT_MODIFIED, send(&vault.cx, request).await);
+
+        let request = Request::builder()
+            .uri("/pages/sub/data.bin")
+            .header("Accept-Encoding", "gzip")
+            .header("Range", "bytes=-4")
+            .body(axum::body::Body::empty())?;
+        let response = router(vault.cx.clone()).oneshot(request).await.unwrap();
+        assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+        assert!(!response.headers().contains_key("Content-Encoding"));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&b"6789"[..], &body[..]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serves_calendar() -> crate::Result<()> {
+        let vault = vault()?;
+        assert_eq!(StatusCode::OK, status(&vault.cx, "/journal").await);
+        assert_eq!(
+            StatusCode::OK,
+            status(&vault.cx, "/journal?date=2023-10").await
+        );
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            status(&vault.cx, "/journal?date=someday").await
+        );
+
+        let request = Request::builder()
+            .uri("/journal?date=2023-10")
+            .body(axum::body::Body::empty())?;
+        let response = router(vault.cx.clone()).oneshot(request).await.unwrap();
+        let etag = response.headers()["ETag"].clone();
+        let request = Request::builder()
+            .uri("/journal?date=2023-10")
+            .header("If-None-Match", etag)
+            .body(axum::body::Body::empty())?;
+        assert_eq!(StatusCode::NOT_MODIFIED, send(&vault.cx, request).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn links_journals() -> crate::Result<()> {
+        let vault = vault()?;
+        let journals = vault.cx.config.root_dir.join("journals");
+        std::fs::create_dir_all(&journals)?;
+        std::fs::write(journals.join("2023-10-20.md"), "# Friday")?;
+        std::fs::write(journals.join("2023-10-21.md"), "# Saturday")?;
+
+        let page = || async {
+            let request = Request::builder()
+                .uri("/pages/journals/2023-10-21.md")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = router(vault.cx.clone()).oneshot(request).await.unwrap();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            String::from_utf8_lossy(&body).into_owned()
+        };
+        let body = page().await;
+        assert!(body.contains(
+            r#"<li class="journal-previous"><a href="/pages/journals/2023-10-20.md">2023-10-20</a></li>"#
+        ));
+        assert!(!body.contains("journal-parent"));
+
+        std::fs::write(journals.join("2023-10.md"), "# October")?;
+        assert!(page().await.contains(
+            r#"<li class="journal-parent"><a href="/pages/journals/2023-10.md">2023-10</a></li>"#
+        ));
+        Ok(())
+    }
 }
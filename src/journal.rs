@@ -1,12 +1,18 @@
 use std::path::Path;
 use std::path::PathBuf;
 
-#[derive(Clone, Debug, clap::ValueEnum)]
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+/// Period of a journal. Periods other than the built-in ones are configured under
+/// `[journal.periods]`, e.g. sprints.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Period {
     Daily,
     Weekly,
     Monthly,
+    Quarterly,
     Yearly,
+    Custom(String),
 }
 
 static ALL_JOURNAL_PERIODS: &[Period] = &[
@@ -16,40 +22,582 @@ static ALL_JOURNAL_PERIODS: &[Period] = &[
     Period::Yearly,
 ];
 
+/// Template of the reviews when `[journal.review]` doesn't have one.
+static DEFAULT_REVIEW: &str = include_str!("../assets/review.md");
+
+static BUILTIN_PERIODS: &[Period] = &[
+    Period::Daily,
+    Period::Weekly,
+    Period::Monthly,
+    Period::Quarterly,
+    Period::Yearly,
+];
+
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let period = match s.to_ascii_lowercase().as_str() {
+            "" => return Err(String::from("Empty period")),
+            "daily" => Period::Daily,
+            "weekly" => Period::Weekly,
+            "monthly" => Period::Monthly,
+            "quarterly" => Period::Quarterly,
+            "yearly" => Period::Yearly,
+            _ => Period::Custom(String::from(s)),
+        };
+        Ok(period)
+    }
+}
+
+impl std::fmt::Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+            Period::Monthly => "monthly",
+            Period::Quarterly => "quarterly",
+            Period::Yearly => "yearly",
+            Period::Custom(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+impl Period {
+    /// Whether the period is built in or configured.
+    pub fn is_known(&self, ctx: &crate::Context) -> bool {
+        self.format(ctx).is_some()
+    }
+
+    /// Moves `date` by `n` periods, e.g. to the same day of the previous week for `-1`. Days
+    /// missing from the target month are clamped to its last day.
+    pub fn offset(&self, ctx: &crate::Context, date: NaiveDate, n: i32) -> NaiveDate {
+        let months = match self {
+            Period::Daily => return date + Duration::days(n.into()),
+            Period::Weekly => return date + Duration::weeks(n.into()),
+            Period::Monthly => n,
+            Period::Quarterly => n * 3,
+            Period::Yearly => n * 12,
+            Period::Custom(name) => {
+                let Some(custom) = ctx.config.journal.periods.get(name) else {
+                    return date;
+                };
+                return date + Duration::days(custom.length() * i64::from(n));
+            }
+        };
+
+        let shifted = if months < 0 {
+            date.checked_sub_months(Months::new(months.unsigned_abs()))
+        } else {
+            date.checked_add_months(Months::new(months.unsigned_abs()))
+        };
+        shifted.unwrap_or(date)
+    }
+
+    /// First day of the period containing `date`.
+    pub fn start(&self, ctx: &crate::Context, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Daily => date,
+            Period::Weekly => crate::date::start_of_week(date, ctx.config.journal.week_start),
+            Period::Monthly => date.with_day(1).unwrap_or(date),
+            Period::Quarterly => {
+                NaiveDate::from_ymd_opt(date.year(), (date.month0() / 3) * 3 + 1, 1).unwrap_or(date)
+            }
+            Period::Yearly => date.with_ordinal(1).unwrap_or(date),
+            Period::Custom(name) => match ctx.config.journal.periods.get(name) {
+                Some(custom) => {
+                    let days = (date - custom.anchor).num_days();
+                    custom.anchor
+                        + Duration::days(days.div_euclid(custom.length()) * custom.length())
+                }
+                None => date,
+            },
+        }
+    }
+
+    /// Last day of the period containing `date`.
+    pub fn end(&self, ctx: &crate::Context, date: NaiveDate) -> NaiveDate {
+        self.offset(ctx, self.start(ctx, date), 1) - Duration::days(1)
+    }
+
+    /// The built-in period containing this one, e.g. the week of a day.
+    pub fn parent(&self) -> Option<Period> {
+        match self {
+            Period::Daily => Some(Period::Weekly),
+            Period::Weekly => Some(Period::Monthly),
+            Period::Monthly => Some(Period::Quarterly),
+            Period::Quarterly => Some(Period::Yearly),
+            Period::Yearly | Period::Custom(_) => None,
+        }
+    }
+
+    fn format<'a>(&self, ctx: &'a crate::Context) -> Option<&'a str> {
+        let path_format = &ctx.config.journal.path;
+        let format = match self {
+            Period::Daily => &path_format.daily,
+            Period::Weekly => &path_format.weekly,
+            Period::Monthly => &path_format.monthly,
+            Period::Quarterly => &path_format.quarterly,
+            Period::Yearly => &path_format.yearly,
+            Period::Custom(name) => &ctx.config.journal.periods.get(name)?.path,
+        };
+        Some(format)
+    }
+
+    /// Path of the journal of the period containing `date`, relative to the root directory. It's
+    /// the one of the first day of the period, so that a week isn't split in two files around New
+    /// Year and custom periods, which have no specifier, are named after their first day.
+    pub(crate) fn path(&self, ctx: &crate::Context, date: NaiveDate) -> Option<String> {
+        let format = self.format(ctx)?;
+        Some(format_date(format, self.start(ctx, date)))
+    }
+
+    /// First day of the journal at `path`, relative to the root directory, if it's one of the
+    /// period.
+    fn parse(&self, ctx: &crate::Context, path: &str) -> Option<NaiveDate> {
+        let start = self.start(ctx, parse_date(self.format(ctx)?, path)?);
+        (self.path(ctx, start)? == path).then_some(start)
+    }
+}
+
+/// `args_periods`, or the daily, weekly, monthly and yearly periods if it's empty.
+pub fn selected(args_periods: &[Period]) -> &[Period] {
+    if args_periods.is_empty() {
+        ALL_JOURNAL_PERIODS
+    } else {
+        args_periods
+    }
+}
+
+/// Built-in periods followed by the configured ones.
+fn known_periods(ctx: &crate::Context) -> impl Iterator<Item = Period> + '_ {
+    BUILTIN_PERIODS.iter().cloned().chain(
+        ctx.config
+            .journal
+            .periods
+            .keys()
+            .map(|name| Period::Custom(name.clone())),
+    )
+}
+
+/// Links to the journals of `date` for every known period, by period, from a note of `dir`
+/// relative to the root directory.
+pub fn links(
+    ctx: &crate::Context,
+    date: NaiveDate,
+    dir: &Path,
+) -> std::collections::BTreeMap<String, String> {
+    known_periods(ctx)
+        .filter_map(|period| Some((period.to_string(), link(ctx, &period, date, dir)?)))
+        .collect()
+}
+
+/// Link to the journal of `period` for `date` from a note of `dir` relative to the root
+/// directory.
+fn link(ctx: &crate::Context, period: &Period, date: NaiveDate, dir: &Path) -> Option<String> {
+    let path = period.path(ctx, date)?;
+    Some(relative_link(dir, Path::new(&path)))
+}
+
+/// Paths of the journals of `date`. Unknown periods are left out.
 pub fn paths(
     ctx: &crate::Context,
     date: chrono::NaiveDate,
-    args_periods: &Vec<Period>,
-    root_dir: &PathBuf,
+    args_periods: &[Period],
+    root_dir: &Path,
 ) -> Vec<PathBuf> {
-    let path_format = &ctx.config.journal.path;
-    let formats = [
-        &path_format.daily,
-        &path_format.weekly,
-        &path_format.monthly,
-        &path_format.yearly,
-    ];
+    selected(args_periods)
+        .iter()
+        .filter_map(|period| period.path(ctx, date))
+        .map(|path| root_dir.join(Path::new(&path)))
+        .collect()
+}
 
-    let [daily_path, weekly_path, monthly_path, yearly_path] =
-        formats.map(|format| date.format(format.as_str()).to_string());
+/// Same as [`paths`] with `date` moved by `offset` of each period, e.g. the previous day, week,
+/// month and year for `-1`.
+pub fn offset_paths(
+    ctx: &crate::Context,
+    date: NaiveDate,
+    offset: i32,
+    args_periods: &[Period],
+    root_dir: &Path,
+) -> Vec<PathBuf> {
+    selected(args_periods)
+        .iter()
+        .flat_map(|period| {
+            let date = period.offset(ctx, date, offset);
+            paths(ctx, date, std::slice::from_ref(period), root_dir)
+        })
+        .collect()
+}
 
-    let periods = if args_periods.is_empty() {
-        ALL_JOURNAL_PERIODS
-    } else {
-        args_periods.as_slice()
+/// Paths of the journals of the periods overlapping the days from `from` to `to`, included, in the
+/// order of the periods then of the dates.
+pub fn range_paths(
+    ctx: &crate::Context,
+    from: NaiveDate,
+    to: NaiveDate,
+    args_periods: &[Period],
+    root_dir: &Path,
+) -> Vec<PathBuf> {
+    let mut range_paths = Vec::new();
+    for period in selected(args_periods) {
+        let mut date = period.start(ctx, from);
+        while date <= to {
+            range_paths.extend(paths(ctx, date, std::slice::from_ref(period), root_dir));
+            let next = period.offset(ctx, date, 1);
+            // Unknown periods don't move.
+            if next <= date {
+                break;
+            }
+            date = next;
+        }
+    }
+    range_paths
+}
+
+/// Period and date of the journal at `path`, or `None` if it isn't one. The date is the first
+/// day of the period matching the path.
+pub fn parse_path(ctx: &crate::Context, path: &Path) -> Option<(Period, NaiveDate)> {
+    let path = crate::http::relative_path(&ctx.config.root_dir, path);
+    known_periods(ctx).find_map(|period| period.parse(ctx, &path).map(|date| (period, date)))
+}
+
+/// Period, first and last days of the journal at `path`, or `None` if it isn't one.
+pub fn which(ctx: &crate::Context, path: &Path) -> Option<(Period, NaiveDate, NaiveDate)> {
+    let (period, start) = parse_path(ctx, path)?;
+    let end = period.end(ctx, start);
+    Some((period, start, end))
+}
+
+/// The journal of the nearest larger period containing the one at `path`, e.g. the week of a
+/// day. Larger periods are skipped when their journal doesn't exist, e.g. the quarter of a month
+/// for those who don't write quarterly journals. When none exists, it's the journal of the nearest
+/// larger period, to be created.
+pub fn parent(ctx: &crate::Context, path: &Path) -> Option<PathBuf> {
+    let (period, start) = parse_path(ctx, path)?;
+    let parents: Vec<PathBuf> = std::iter::successors(period.parent(), Period::parent)
+        .filter_map(|parent| parent.path(ctx, start))
+        .map(|parent| ctx.config.root_dir.join(parent))
+        .collect();
+
+    let existing = parents.iter().find(|parent| parent.exists());
+    existing.or(parents.first()).cloned()
+}
+
+/// The existing journals of the nearest smaller period having some in the one at `path`, e.g. the
+/// days of a week, ordered by date. Custom periods don't line up with the built-in ones, so their
+/// children are the days.
+pub fn children(ctx: &crate::Context, path: &Path) -> Vec<PathBuf> {
+    let Some((period, start, end)) = which(ctx, path) else {
+        return Vec::new();
+    };
+
+    // The smaller periods, from the nearest.
+    let smaller = BUILTIN_PERIODS.iter().rev().filter(|child| match period {
+        Period::Custom(_) => **child == Period::Daily,
+        _ => std::iter::successors(child.parent(), Period::parent).any(|parent| parent == period),
+    });
+    for child in smaller {
+        let mut children: Vec<(NaiveDate, PathBuf)> = existing(ctx, child)
+            .filter(|(date, _)| start <= *date && *date <= end)
+            .collect();
+        if !children.is_empty() {
+            children.sort();
+            return children.into_iter().map(|(_, path)| path).collect();
+        }
+    }
+
+    Vec::new()
+}
+
+/// The nearest existing journal of the same period before the one at `path`.
+pub fn previous(ctx: &crate::Context, path: &Path) -> Option<PathBuf> {
+    let (period, date) = parse_path(ctx, path)?;
+    existing(ctx, &period)
+        .filter(|(other, _)| *other < date)
+        .max_by_key(|(other, _)| *other)
+        .map(|(_, path)| path)
+}
+
+/// The nearest existing journal of the same period after the one at `path`.
+pub fn next(ctx: &crate::Context, path: &Path) -> Option<PathBuf> {
+    let (period, date) = parse_path(ctx, path)?;
+    existing(ctx, &period)
+        .filter(|(other, _)| *other > date)
+        .min_by_key(|(other, _)| *other)
+        .map(|(_, path)| path)
+}
+
+/// Creates the journal of `period` for `date` unless it exists, from the template configured
+/// for the period in `[journal.templates]` if any. New daily journals get the unfinished tasks of
+/// the last one when `[journal.rollover]` is enabled. Returns the path of the journal.
+pub fn create(ctx: &crate::Context, period: &Period, date: NaiveDate) -> crate::Result<PathBuf> {
+    let root_dir = &ctx.config.root_dir;
+    let path = period
+        .path(ctx, date)
+        .ok_or_else(|| format!("Unknown period: {}", period))?;
+    let filepath = root_dir.join(&path);
+    if filepath.exists() {
+        return Ok(filepath);
+    }
+
+    let mut content = match ctx.config.journal.templates.get(&period.to_string()) {
+        Some(template) => {
+            // Templates live in the same directory as the ones of `noteutil template`.
+            let template_path = root_dir.join("templates").join(template);
+            let mut tera = tera::Tera::default();
+            tera.add_template_file(&template_path, Some(template))?;
+            tera.render(template, &template_context(ctx, period, date, &filepath)?)?
+        }
+        None => String::new(),
     };
 
-    let _paths: Vec<&str> = periods
+    let rollover = &ctx.config.journal.rollover;
+    let mut migrated = None;
+    if rollover.enabled && *period == Period::Daily {
+        if let Some(last) = previous(ctx, &filepath) {
+            let last_content = std::fs::read_to_string(&last)?;
+            let tasks = crate::tasks::tasks(&last_content, false);
+            if !tasks.is_empty() {
+                let markdown: Vec<&str> = tasks.iter().map(|task| task.markdown.as_str()).collect();
+                content = crate::tasks::insert_into_section(
+                    &content,
+                    &rollover.section,
+                    &markdown.join("\n"),
+                );
+                let marked = crate::tasks::mark_migrated(&last_content, &tasks);
+                migrated = Some((last, last_content, marked));
+            }
+        }
+    }
+
+    // Write the whole journal at once and never replace one created in the meantime.
+    let mut file = crate::note::temp_file_for(&filepath)?;
+    std::io::Write::write_all(&mut file, content.as_bytes())?;
+    if let Err(err) = file.persist_noclobber(&filepath) {
+        if !filepath.exists() {
+            return Err(err.error.into());
+        }
+        return Ok(filepath);
+    }
+
+    // The last journal may have been edited since it was read, e.g. in the editor: its tasks
+    // are then left unmarked rather than losing the edit.
+    if let Some((last, read, marked)) = migrated.filter(|_| rollover.mark_migrated) {
+        if !crate::note::replace(&last, marked.as_bytes(), Some(read.as_bytes()))? {
+            log::warn!(
+                "{} changed, its tasks are not marked as migrated",
+                last.display()
+            );
+        }
+    }
+
+    Ok(filepath)
+}
+
+/// Variables of the template of a journal: the ones of [`crate::template::context`] for the
+/// journal, with its `period`, the `start` and `end` of the period and links to the `previous`,
+/// `next` and `parent` journals, relative to the new one.
+fn template_context(
+    ctx: &crate::Context,
+    period: &Period,
+    date: NaiveDate,
+    filepath: &Path,
+) -> crate::Result<tera::Context> {
+    let target = crate::template::Target {
+        date,
+        path: Some(filepath.to_path_buf()),
+        ..Default::default()
+    };
+    let mut context = crate::template::context(ctx, &target)?;
+
+    let path = crate::http::relative_path(&ctx.config.root_dir, filepath);
+    let dir = Path::new(&path).parent().unwrap_or(Path::new(""));
+    context.insert("period", &period.to_string());
+    context.insert("start", &period.start(ctx, date).to_string());
+    context.insert("end", &period.end(ctx, date).to_string());
+    context.insert(
+        "previous",
+        &link(ctx, period, period.offset(ctx, date, -1), dir),
+    );
+    context.insert("next", &link(ctx, period, period.offset(ctx, date, 1), dir));
+    context.insert(
+        "parent",
+        &period
+            .parent()
+            .and_then(|parent| link(ctx, &parent, date, dir)),
+    );
+    Ok(context)
+}
+
+/// Link to `path` from a note of `dir`, both relative to the root directory.
+fn relative_link(dir: &Path, path: &Path) -> String {
+    pathdiff::diff_paths(path, dir)
+        .unwrap_or_else(|| PathBuf::from(path))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// A journal gathered by a review.
+#[derive(serde::Serialize)]
+struct Reviewed {
+    title: String,
+    date: String,
+    /// Link from the reviewed journal.
+    path: String,
+    tasks: Vec<String>,
+    /// Sections of `[journal.review]` by title, `None` when missing or empty.
+    sections: std::collections::BTreeMap<String, Option<String>>,
+}
+
+/// Summary of the journals in the period of `date`, e.g. the days of a week or the weeks of a
+/// month: their completed tasks and the sections of `[journal.review]`, rendered with its
+/// template or a default one.
+pub fn review(ctx: &crate::Context, period: &Period, date: NaiveDate) -> crate::Result<String> {
+    let root_dir = &ctx.config.root_dir;
+    let review = &ctx.config.journal.review;
+    let path = period
+        .path(ctx, date)
+        .ok_or_else(|| format!("Unknown period: {}", period))?;
+    let dir = Path::new(&path).parent().unwrap_or(Path::new(""));
+
+    let titles: Vec<&str> = review
+        .sections
         .iter()
-        .map(|period| match period {
-            Period::Daily => daily_path.as_str(),
-            Period::Weekly => weekly_path.as_str(),
-            Period::Monthly => monthly_path.as_str(),
-            Period::Yearly => yearly_path.as_str(),
-        })
+        .map(|section| section.trim_start_matches('#').trim())
         .collect();
+    let mut journals = Vec::new();
+    for child in children(ctx, &root_dir.join(&path)) {
+        let content = std::fs::read_to_string(&child)?;
+        let note = crate::Note::build_from_str(&child, &content)?;
+        let Some((_, child_date)) = parse_path(ctx, &child) else {
+            continue;
+        };
+        let child_path = crate::http::relative_path(root_dir, &child);
+        journals.push(Reviewed {
+            title: note.title,
+            date: child_date.to_string(),
+            path: relative_link(dir, Path::new(&child_path)),
+            tasks: crate::tasks::completed(&content)
+                .into_iter()
+                .map(|task| task.markdown)
+                .collect(),
+            sections: titles
+                .iter()
+                .map(|title| (title.to_string(), crate::tasks::section(&content, title)))
+                .collect(),
+        });
+    }
+
+    let mut context = tera::Context::new();
+    context.insert("date", &date.to_string());
+    context.insert("period", &period.to_string());
+    context.insert("start", &period.start(ctx, date).to_string());
+    context.insert("end", &period.end(ctx, date).to_string());
+    context.insert("path", &path);
+    context.insert("sections", &titles);
+    let tasks: Vec<&String> = journals.iter().flat_map(|journal| &journal.tasks).collect();
+    context.insert("tasks", &tasks);
+    context.insert("journals", &journals);
+
+    let mut tera = tera::Tera::default();
+    let name = match &review.template {
+        Some(template) => {
+            tera.add_template_file(root_dir.join("templates").join(template), Some(template))?;
+            template.as_str()
+        }
+        None => {
+            tera.add_raw_template("review.md", DEFAULT_REVIEW)?;
+            "review.md"
+        }
+    };
+    Ok(tera.render(name, &context)?)
+}
 
-    _paths.iter().map(|p| root_dir.join(Path::new(p))).collect()
+/// The journals of `period` found under the root directory, with their dates.
+fn existing<'a>(
+    ctx: &'a crate::Context,
+    period: &Period,
+) -> impl Iterator<Item = (NaiveDate, PathBuf)> + 'a {
+    let root_dir = &ctx.config.root_dir;
+    let format = period.format(ctx).unwrap_or_default();
+    // Only walk the directory holding all the journals of the period.
+    let fixed = &format[..format.find('%').unwrap_or(format.len())];
+    let dir = root_dir.join(&fixed[..fixed.rfind('/').unwrap_or(0)]);
+
+    let period = period.clone();
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(move |e| {
+            let path = crate::http::relative_path(root_dir, e.path());
+            period.parse(ctx, &path).map(|date| (date, e.into_path()))
+        })
+}
+
+/// Formats `date` like chrono, with `%q` for the quarter as chrono doesn't have it.
+fn format_date(format: &str, date: NaiveDate) -> String {
+    let format = with_quarter(format, date.month0() / 3 + 1);
+    date.format(&format).to_string()
+}
+
+/// Replaces `%q` with `quarter` in `format`.
+fn with_quarter(format: &str, quarter: u32) -> String {
+    let mut replaced = String::with_capacity(format.len());
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('%', Some('q')) => replaced.push_str(&quarter.to_string()),
+            ('%', Some(next)) => {
+                replaced.push('%');
+                replaced.push(next);
+            }
+            _ => {
+                replaced.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    replaced
+}
+
+/// Inverts `format` on `path`. Formats leaving out the day, e.g. `%Y-%m` or `%Y-w%U`, are
+/// completed with the first day of the period.
+fn parse_date(format: &str, path: &str) -> Option<NaiveDate> {
+    let completions: &[(&str, &[&str])] = &[
+        ("", &[""]),
+        (" %d", &[" 1"]),
+        (" %m %d", &[" 1 1", " 4 1", " 7 1", " 10 1"]),
+        (" %w", &[" 0", " 1", " 2", " 3", " 4", " 5", " 6"]),
+    ];
+    let formats: Vec<String> = match format.contains("%q") {
+        true => (1..=4)
+            .map(|quarter| with_quarter(format, quarter))
+            .collect(),
+        false => vec![String::from(format)],
+    };
+
+    completions.iter().find_map(|(suffix, values)| {
+        formats
+            .iter()
+            .flat_map(|parsed_format| {
+                values.iter().filter_map(move |value| {
+                    NaiveDate::parse_from_str(
+                        &format!("{}{}", path, value),
+                        &format!("{}{}", parsed_format, suffix),
+                    )
+                    .ok()
+                })
+            })
+            // Reject the dates that don't give back the path, e.g. a day out of its week.
+            .filter(|date| format_date(format, *date) == path)
+            .min()
+    })
 }
 
 #[cfg(test)]
@@ -60,7 +608,7 @@ mod paths_tests {
     #[test]
     fn no_periods() -> Result<(), Box<dyn Error>> {
         assert_eq!(
-            vec![
+            [
                 "./journals/2023-10-21.md",
                 "./journals/2023-w42.md",
                 "./journals/2023-10.md",
@@ -73,7 +621,7 @@ mod paths_tests {
                 &crate::Context::default(),
                 chrono::NaiveDate::from_ymd_opt(2023, 10, 21).unwrap(),
                 &Vec::new(),
-                &Path::new(".").to_path_buf(),
+                Path::new("."),
             ),
         );
 
@@ -83,17 +631,455 @@ mod paths_tests {
     #[test]
     fn one_period() -> Result<(), Box<dyn Error>> {
         assert_eq!(
-            vec!["./journals/2023-10-21.md"]
+            ["./journals/2023-10-21.md"]
                 .iter()
                 .map(|p| Path::new(p).to_path_buf())
                 .collect::<Vec<PathBuf>>(),
             paths(
                 &crate::Context::default(),
                 chrono::NaiveDate::from_ymd_opt(2023, 10, 21).unwrap(),
-                &vec![Period::Daily],
-                &Path::new(".").to_path_buf(),
+                &[Period::Daily],
+                Path::new("."),
             ),
         );
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod navigation_tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn offsets() {
+        let ctx = crate::Context::default();
+        assert_eq!(
+            date(2023, 12, 31),
+            Period::Daily.offset(&ctx, date(2024, 1, 1), -1)
+        );
+        assert_eq!(
+            date(2023, 10, 28),
+            Period::Weekly.offset(&ctx, date(2023, 10, 21), 1)
+        );
+        assert_eq!(
+            date(2024, 2, 29),
+            Period::Monthly.offset(&ctx, date(2024, 3, 31), -1)
+        );
+        assert_eq!(
+            date(2025, 2, 28),
+            Period::Yearly.offset(&ctx, date(2024, 2, 29), 1)
+        );
+
+        assert_eq!(
+            vec![
+                PathBuf::from("journals/2023-10-14.md"),
+                PathBuf::from("journals/2023-09.md"),
+            ],
+            offset_paths(
+                &crate::Context::default(),
+                date(2023, 10, 15),
+                -1,
+                &[Period::Daily, Period::Monthly],
+                Path::new(""),
+            ),
+        );
+    }
+
+    #[test]
+    fn parses_paths() {
+        let ctx = crate::Context::default();
+        let parse = |path: &str| parse_path(&ctx, Path::new(path));
+
+        assert_eq!(
+            Some((Period::Daily, date(2023, 10, 21))),
+            parse("journals/2023-10-21.md")
+        );
+        assert_eq!(
+            Some((Period::Weekly, date(2023, 10, 15))),
+            parse("journals/2023-w42.md")
+        );
+        assert_eq!(
+            Some((Period::Weekly, date(2023, 1, 1))),
+            parse("journals/2023-w01.md")
+        );
+        assert_eq!(
+            Some((Period::Monthly, date(2023, 10, 1))),
+            parse("journals/2023-10.md")
+        );
+        assert_eq!(
+            Some((Period::Yearly, date(2023, 1, 1))),
+            parse("journals/2023.md")
+        );
+        assert_eq!(None, parse("journals/2023-13.md"));
+        assert_eq!(None, parse("notes/2023-10-21.md"));
+    }
+
+    #[test]
+    fn navigates() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journals = dir.path().join("journals");
+        std::fs::create_dir_all(&journals)?;
+        for name in [
+            "2023-10-02.md",
+            "2023-10-05.md",
+            "2023-10-09.md",
+            "2023-10.md",
+        ] {
+            std::fs::write(journals.join(name), "")?;
+        }
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+
+        let current = journals.join("2023-10-05.md");
+        assert_eq!(
+            Some(journals.join("2023-10-02.md")),
+            previous(&ctx, &current)
+        );
+        assert_eq!(Some(journals.join("2023-10-09.md")), next(&ctx, &current));
+        // The journal itself doesn't need to exist.
+        assert_eq!(
+            Some(journals.join("2023-10-09.md")),
+            previous(&ctx, Path::new("journals/2023-10-31.md"))
+        );
+        assert_eq!(None, next(&ctx, &journals.join("2023-10-09.md")));
+        assert_eq!(None, next(&ctx, &journals.join("2023-10.md")));
+        Ok(())
+    }
+
+    #[test]
+    fn quarters() {
+        let ctx = crate::Context::default();
+        assert_eq!(
+            vec![PathBuf::from("journals/2023-q4.md")],
+            paths(
+                &ctx,
+                date(2023, 10, 21),
+                &[Period::Quarterly],
+                Path::new("")
+            ),
+        );
+        assert_eq!(
+            date(2023, 7, 1),
+            Period::Quarterly.start(&ctx, date(2023, 9, 30))
+        );
+        assert_eq!(
+            Some((Period::Quarterly, date(2023, 4, 1))),
+            parse_path(&ctx, Path::new("journals/2023-q2.md"))
+        );
+        assert_eq!("%%q 1%%", with_quarter("%%q %q%%", 1));
+    }
+
+    #[test]
+    fn custom_periods() -> crate::Result<()> {
+        let mut ctx = crate::Context::default();
+        ctx.config.journal.periods.insert(
+            String::from("sprint"),
+            crate::config::CustomPeriod {
+                path: String::from("sprints/%Y-%m-%d.md"),
+                anchor: date(2026, 1, 5),
+                weeks: 2,
+                ..Default::default()
+            },
+        );
+        let sprint: Period = "sprint".parse()?;
+
+        assert!(sprint.is_known(&ctx));
+        assert!(!Period::Custom(String::from("unknown")).is_known(&ctx));
+        assert_eq!(date(2026, 10, 12), sprint.start(&ctx, date(2026, 10, 21)));
+        assert_eq!(date(2025, 12, 22), sprint.start(&ctx, date(2026, 1, 4)));
+        assert_eq!(
+            vec![PathBuf::from("sprints/2026-10-26.md")],
+            offset_paths(
+                &ctx,
+                date(2026, 10, 21),
+                1,
+                std::slice::from_ref(&sprint),
+                Path::new("")
+            ),
+        );
+        assert_eq!(
+            Some((sprint.clone(), date(2026, 10, 12))),
+            parse_path(&ctx, Path::new("sprints/2026-10-12.md"))
+        );
+        // Not the first day of a sprint.
+        assert_eq!(None, parse_path(&ctx, Path::new("sprints/2026-10-13.md")));
+        Ok(())
+    }
+
+    #[test]
+    fn weeks() {
+        let mut ctx = crate::Context::default();
+        let weekly =
+            |ctx: &crate::Context, date| paths(ctx, date, &[Period::Weekly], Path::new(""));
+
+        // The week of New Year is the last one of 2021 rather than the week 0 of 2022.
+        assert_eq!(
+            vec![PathBuf::from("journals/2021-w52.md")],
+            weekly(&ctx, date(2022, 1, 1))
+        );
+
+        ctx.config.journal.week_start = chrono::Weekday::Mon;
+        ctx.config.journal.path.weekly = String::from("journals/%G-W%V.md");
+        assert_eq!(
+            vec![PathBuf::from("journals/2020-W53.md")],
+            weekly(&ctx, date(2021, 1, 3))
+        );
+        assert_eq!(
+            vec![PathBuf::from("journals/2026-W01.md")],
+            weekly(&ctx, date(2025, 12, 29))
+        );
+        assert_eq!(
+            Some((Period::Weekly, date(2020, 12, 28))),
+            parse_path(&ctx, Path::new("journals/2020-W53.md"))
+        );
+        assert_eq!(
+            date(2026, 1, 5),
+            Period::Weekly.offset(&ctx, date(2025, 12, 29), 1)
+        );
+    }
+
+    #[test]
+    fn creates_from_templates() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("templates"))?;
+        std::fs::write(
+            dir.path().join("templates/daily.md"),
+            "# {{ date }} ({{ period }}, {{ start }} to {{ end }})\n\
+             [Previous]({{ previous }}) [Next]({{ next }}) [Week]({{ parent }}) \
+             [Year]({{ journals.yearly }}) {{ title }} {{ config.journal.week_start }}\n",
+        )?;
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+        ctx.config
+            .journal
+            .templates
+            .insert(String::from("daily"), String::from("daily.md"));
+
+        let filepath = create(&ctx, &Period::Daily, date(2023, 10, 21))?;
+        assert_eq!(dir.path().join("journals/2023-10-21.md"), filepath);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&filepath)?.permissions().mode();
+            assert_eq!(0o644, mode & 0o777);
+        }
+        assert_eq!(
+            "# 2023-10-21 (daily, 2023-10-21 to 2023-10-21)\n\
+             [Previous](2023-10-20.md) [Next](2023-10-22.md) [Week](2023-w42.md) \
+             [Year](2023.md) 2023-10-21 Sun\n",
+            std::fs::read_to_string(&filepath)?
+        );
+
+        // Existing journals are left untouched.
+        std::fs::write(&filepath, "Edited")?;
+        create(&ctx, &Period::Daily, date(2023, 10, 21))?;
+        assert_eq!("Edited", std::fs::read_to_string(&filepath)?);
+
+        // Periods without a template get an empty journal.
+        let filepath = create(&ctx, &Period::Monthly, date(2023, 10, 21))?;
+        assert_eq!("", std::fs::read_to_string(filepath)?);
+        assert_eq!(
+            date(2023, 10, 31),
+            Period::Monthly.end(&ctx, date(2023, 10, 21))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rolls_tasks_over() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journals = dir.path().join("journals");
+        std::fs::create_dir_all(&journals)?;
+        std::fs::write(journals.join("2023-10-19.md"), "# Thursday\n\n- [ ] Old\n")?;
+        std::fs::write(
+            journals.join("2023-10-20.md"),
+            "# Friday\n\n- [x] Done\n- [ ] Review\n  - [x] Read\n",
+        )?;
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+        ctx.config.journal.rollover.enabled = true;
+        ctx.config.journal.rollover.mark_migrated = true;
+
+        let filepath = create(&ctx, &Period::Daily, date(2023, 10, 23))?;
+        assert_eq!(
+            "## Tasks\n\n- [ ] Review\n  - [x] Read\n",
+            std::fs::read_to_string(filepath)?
+        );
+        assert_eq!(
+            "# Friday\n\n- [x] Done\n- [>] Review\n  - [x] Read\n",
+            std::fs::read_to_string(journals.join("2023-10-20.md"))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ranges() {
+        let ctx = crate::Context::default();
+        let range = |periods: &[Period]| {
+            range_paths(
+                &ctx,
+                date(2023, 9, 29),
+                date(2023, 10, 2),
+                periods,
+                Path::new(""),
+            )
+        };
+
+        assert_eq!(
+            vec![
+                PathBuf::from("journals/2023-09-29.md"),
+                PathBuf::from("journals/2023-09-30.md"),
+                PathBuf::from("journals/2023-10-01.md"),
+                PathBuf::from("journals/2023-10-02.md"),
+            ],
+            range(&[Period::Daily])
+        );
+        assert_eq!(
+            vec![
+                PathBuf::from("journals/2023-w39.md"),
+                PathBuf::from("journals/2023-w40.md"),
+                PathBuf::from("journals/2023-09.md"),
+                PathBuf::from("journals/2023-10.md"),
+            ],
+            range(&[Period::Weekly, Period::Monthly])
+        );
+        assert!(range_paths(
+            &ctx,
+            date(2023, 10, 2),
+            date(2023, 9, 29),
+            &[Period::Daily],
+            Path::new("")
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn parents_and_children() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journals = dir.path().join("journals");
+        std::fs::create_dir_all(&journals)?;
+        for name in ["2023-10-16.md", "2023-10-02.md", "2023-w42.md", "2023.md"] {
+            std::fs::write(journals.join(name), "")?;
+        }
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+
+        assert_eq!(
+            Some((Period::Weekly, date(2023, 10, 15), date(2023, 10, 21))),
+            which(&ctx, &journals.join("2023-w42.md"))
+        );
+        assert_eq!(
+            Some((Period::Quarterly, date(2023, 10, 1), date(2023, 12, 31))),
+            which(&ctx, Path::new("journals/2023-q4.md"))
+        );
+        assert_eq!(None, which(&ctx, Path::new("notes/todo.md")));
+
+        assert_eq!(
+            Some(journals.join("2023-w42.md")),
+            parent(&ctx, &journals.join("2023-10-16.md"))
+        );
+        // Neither the month nor the quarter exist.
+        assert_eq!(
+            Some(journals.join("2023.md")),
+            parent(&ctx, &journals.join("2023-w42.md"))
+        );
+        // None of them exist.
+        assert_eq!(
+            Some(journals.join("2022-10.md")),
+            parent(&ctx, &journals.join("2022-w40.md"))
+        );
+        assert_eq!(None, parent(&ctx, &journals.join("2023.md")));
+
+        assert_eq!(
+            vec![journals.join("2023-10-16.md")],
+            children(&ctx, &journals.join("2023-w42.md"))
+        );
+        assert_eq!(
+            vec![journals.join("2023-10-02.md")],
+            children(&ctx, &journals.join("2023-w40.md"))
+        );
+        // The weeks rather than the days.
+        assert_eq!(
+            vec![journals.join("2023-w42.md")],
+            children(&ctx, &journals.join("2023-10.md"))
+        );
+        assert_eq!(
+            vec![journals.join("2023-w42.md")],
+            children(&ctx, &journals.join("2023.md"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reviews() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journals = dir.path().join("journals");
+        std::fs::create_dir_all(&journals)?;
+        std::fs::write(
+            journals.join("2023-10-16.md"),
+            "# Monday\n\n- [x] Review\n  - [ ] Left\n  - [x] Read\n- [ ] Write\n\n## Notes\n\nQuiet day\n",
+        )?;
+        std::fs::write(
+            journals.join("2023-10-18.md"),
+            "# Wednesday\n\n## Done\n\n- [x] Write\n\n## Notes\n",
+        )?;
+        std::fs::write(journals.join("2023-10-23.md"), "- [x] Next week\n")?;
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+        ctx.config.journal.review.sections = vec![String::from("## Notes")];
+
+        assert_eq!(
+            "# Weekly review, 2023-10-15 to 2023-10-21\n\n\
+             ## Completed tasks\n\n\
+             - [x] Review\n  - [x] Read\n\
+             - [x] Write\n\n\
+             ## Notes\n\n\
+             ### [Monday](2023-10-16.md)\n\n\
+             Quiet day\n",
+            review(&ctx, &Period::Weekly, date(2023, 10, 19))?
+        );
+
+        std::fs::create_dir_all(dir.path().join("templates"))?;
+        std::fs::write(
+            dir.path().join("templates/review.md"),
+            "{% for journal in journals %}{{ journal.date }} {{ journal.tasks | length }}\n{% endfor %}",
+        )?;
+        ctx.config.journal.review.template = Some(String::from("review.md"));
+        assert_eq!(
+            "2023-10-16 1\n2023-10-18 1\n",
+            review(&ctx, &Period::Weekly, date(2023, 10, 19))?
+        );
+
+        ctx.config.journal.periods.insert(
+            String::from("sprint"),
+            crate::config::CustomPeriod {
+                path: String::from("sprints/%Y-%m-%d.md"),
+                anchor: date(2023, 10, 9),
+                weeks: 2,
+                ..Default::default()
+            },
+        );
+        let sprint: Period = "sprint".parse()?;
+        assert_eq!(
+            vec![
+                journals.join("2023-10-16.md"),
+                journals.join("2023-10-18.md")
+            ],
+            children(&ctx, &dir.path().join("sprints/2023-10-09.md"))
+        );
+        assert_eq!(
+            "2023-10-16 1\n2023-10-18 1\n",
+            review(&ctx, &sprint, date(2023, 10, 20))?
+        );
+        Ok(())
+    }
+}
@@ -1,18 +1,42 @@
 pub mod journal;
 
 mod note;
+pub use note::Link;
 pub use note::Note;
 
+mod frontmatter;
+pub use frontmatter::FrontMatter;
+
 mod context;
 pub use context::Context;
 
 mod config;
+pub use config::Auth;
 pub use config::Config;
+pub use config::Symlinks;
 
 pub mod date;
 
+pub mod export;
+
+mod documents;
+pub use documents::Documents;
+
+mod api;
+mod asset;
+mod auth;
+mod calendar;
+mod diagram;
+mod highlight;
 mod html;
 pub mod http;
+mod listing;
+mod math;
+mod preview;
+pub mod search;
+mod tasks;
+pub mod template;
+mod theme;
 
 pub mod lsp;
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -0,0 +1,238 @@
+use std::time::SystemTime;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Html,
+    response::{IntoResponse, Response},
+};
+
+use crate::html::escape;
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Sort {
+    #[default]
+    Name,
+    Title,
+    Modified,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Params {
+    sort: Sort,
+    order: Order,
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    title: String,
+    is_dir: bool,
+    modified: Option<SystemTime>,
+}
+
+pub async fn serve_root(
+    State(state): State<crate::http::ServerState>,
+    Query(params): Query<Params>,
+) -> Response {
+    serve_directory(&state, "", &params)
+}
+
+/// Lists the notes, other files and subdirectories of `path`, relative to `root_dir`.
+pub fn serve_directory(state: &crate::http::ServerState, path: &str, params: &Params) -> Response {
+    let path = path.trim_matches('/');
+    let mut entries = match entries(&state.config, path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::error!("{}: Unable to list directory {}", err, path);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to list directory",
+            )
+                .into_response();
+        }
+    };
+    sort(&mut entries, params);
+
+    let base_path = state.config.http.base_path();
+    let breadcrumbs = crate::html::breadcrumbs(&base_path, path);
+    let page = crate::theme::Page {
+        title: breadcrumbs.last().unwrap().name.clone(),
+        body: listing(&base_path, path, &entries, params),
+        breadcrumbs,
+        ..Default::default()
+    };
+    Html(crate::theme::render(&state.config, &page)).into_response()
+}
+
+fn entries(config: &crate::Config, path: &str) -> std::io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for dir_entry in std::fs::read_dir(config.root_dir.join(path))? {
+        let dir_entry = dir_entry?;
+        let Some(name) = dir_entry.file_name().to_str().map(String::from) else {
+            continue;
+        };
+
+        // Skip whatever the page server would refuse to serve.
+        let Some(filepath) = crate::http::resolve_path(config, &join(path, &name)) else {
+            continue;
+        };
+
+        let is_dir = filepath.is_dir();
+        let title = if !is_dir && filepath.extension().is_some_and(|ext| ext == "md") {
+            crate::Note::build(&filepath)
+                .map(|note| note.title)
+                .unwrap_or(name.clone())
+        } else {
+            name.clone()
+        };
+
+        entries.push(Entry {
+            name,
+            title,
+            is_dir,
+            modified: filepath.metadata().and_then(|m| m.modified()).ok(),
+        });
+    }
+
+    Ok(entries)
+}
+
+fn sort(entries: &mut [Entry], params: &Params) {
+    entries.sort_by(|a, b| {
+        let ordering = match params.sort {
+            Sort::Name => a.name.cmp(&b.name),
+            Sort::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            Sort::Modified => a.modified.cmp(&b.modified),
+        };
+
+        match params.order {
+            Order::Asc => ordering,
+            Order::Desc => ordering.reverse(),
+        }
+    });
+
+    // Directories always come first.
+    entries.sort_by_key(|entry| !entry.is_dir);
+}
+
+fn listing(base_path: &str, path: &str, entries: &[Entry], params: &Params) -> String {
+    let mut html = String::new();
+
+    html.push_str("<table class=\"listing\">\n<thead><tr>");
+    for (sort, label) in [
+        (Sort::Title, "Title"),
+        (Sort::Name, "Name"),
+        (Sort::Modified, "Modified"),
+    ] {
+        let order = if params.sort == sort && params.order == Order::Asc {
+            "desc"
+        } else {
+            "asc"
+        };
+        html.push_str(&format!(
+            r#"<th><a href="?sort={}&amp;order={}">{}</a></th>"#,
+            label.to_lowercase(),
+            order,
+            label
+        ));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+
+    for entry in entries {
+        let mut url = crate::html::page_url(base_path, &join(path, &entry.name));
+        let mut title = escape(&entry.title);
+        if entry.is_dir {
+            url.push('/');
+            title.push('/');
+        }
+
+        let modified = entry
+            .modified
+            .map(|modified| {
+                chrono::DateTime::<chrono::Local>::from(modified)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        html.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            escape(&url),
+            title,
+            escape(&entry.name),
+            modified
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html
+}
+
+fn join(dirpath: &str, name: &str) -> String {
+    if dirpath.is_empty() {
+        return String::from(name);
+    }
+
+    format!("{}/{}", dirpath, name)
+}
+
+#[cfg(test)]
+mod listing_tests {
+    use super::*;
+
+    fn vault() -> crate::Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("projects"))?;
+        std::fs::create_dir_all(dir.path().join(".git"))?;
+        std::fs::write(dir.path().join("b.md"), "# Alpha")?;
+        std::fs::write(dir.path().join("a.md"), "# Beta")?;
+        std::fs::write(dir.path().join("image.png"), "")?;
+        Ok(dir)
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_entries() -> crate::Result<()> {
+        let dir = vault()?;
+        let config = crate::Config {
+            root_dir: dir.path().to_path_buf(),
+            ..crate::Config::default()
+        };
+
+        let mut entries = entries(&config, "")?;
+        sort(&mut entries, &Params::default());
+        assert_eq!(
+            vec!["projects", "a.md", "b.md", "image.png"],
+            names(&entries)
+        );
+        assert_eq!("Beta", entries[1].title);
+
+        sort(
+            &mut entries,
+            &Params {
+                sort: Sort::Title,
+                order: Order::Desc,
+            },
+        );
+        assert_eq!(
+            vec!["projects", "image.png", "a.md", "b.md"],
+            names(&entries)
+        );
+
+        Ok(())
+    }
+}
@@ -1,38 +1,30 @@
-use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Arc;
+use std::path::PathBuf;
 
-use tokio::sync::RwLock;
 use tower_lsp::jsonrpc;
 use tower_lsp::lsp_types::*;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct Service {
     config: crate::Config,
-    documents: Arc<RwLock<HashMap<String, String>>>,
+    documents: crate::Documents,
 }
 
-impl Default for Service {
-    fn default() -> Self {
-        Self {
-            config: crate::Config::default(),
-            documents: Arc::new(RwLock::new(HashMap::new())),
+impl Service {
+    async fn replace_document_text(&self, uri: &Url, text: String) {
+        if let Ok(path) = uri.to_file_path() {
+            self.documents.replace(&path, text).await;
         }
     }
-}
 
-impl Service {
-    async fn replace_document_text(&self, uri: &str, text: String) {
-        let mut documents = self.documents.write().await;
-        documents.insert(String::from(uri), text);
+    async fn remove_document(&self, uri: &Url) {
+        if let Ok(path) = uri.to_file_path() {
+            self.documents.remove(&path).await;
+        }
     }
 
-    async fn document_text(&self, uri: &str) -> Option<String> {
-        let documents = self.documents.read().await;
-        match documents.get(uri) {
-            Some(text) => Some(text.clone()),
-            None => None,
-        }
+    async fn document_text(&self, uri: &Url) -> Option<String> {
+        self.documents.text(&uri.to_file_path().ok()?).await
     }
 
     async fn complete_notes(
@@ -45,7 +37,7 @@ impl Service {
             return Ok(());
         }
         let uri = &comp_params.text_document_position.text_document.uri;
-        let text = self.document_text(uri.as_str()).await;
+        let text = self.document_text(uri).await;
         if text.is_none() {
             return Ok(());
         }
@@ -129,15 +121,21 @@ impl tower_lsp::LanguageServer for Backend {
             return;
         }
 
-        let filepath = params.text_document.uri.as_str();
+        let uri = &params.text_document.uri;
         let text = text.unwrap().text.clone();
-        self.service.replace_document_text(filepath, text).await;
+        self.service.replace_document_text(uri, text).await;
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let text = params.text_document.text.clone();
-        let filepath = params.text_document.uri.as_str();
-        self.service.replace_document_text(filepath, text).await;
+        let uri = &params.text_document.uri;
+        self.service.replace_document_text(uri, text).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.service
+            .remove_document(&params.text_document.uri)
+            .await;
     }
 
     async fn shutdown(&self) -> jsonrpc::Result<()> {
@@ -145,15 +143,73 @@ impl tower_lsp::LanguageServer for Backend {
     }
 }
 
+/// Response of the `noteutil/journal` request.
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Journal {
+    period: String,
+    start: String,
+    end: String,
+    previous: Option<Url>,
+    next: Option<Url>,
+    parent: Option<Url>,
+    children: Vec<Url>,
+}
+
+impl Service {
+    fn journal(&self, uri: &Url) -> Option<Journal> {
+        let ctx = crate::Context {
+            config: self.config.clone(),
+            documents: self.documents.clone(),
+        };
+        let path = uri.to_file_path().ok()?;
+        let (period, start, end) = crate::journal::which(&ctx, &path)?;
+        let url = |path: PathBuf| Url::from_file_path(path).ok();
+
+        Some(Journal {
+            period: period.to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+            previous: crate::journal::previous(&ctx, &path).and_then(url),
+            next: crate::journal::next(&ctx, &path).and_then(url),
+            parent: crate::journal::parent(&ctx, &path).and_then(url),
+            children: crate::journal::children(&ctx, &path)
+                .into_iter()
+                .filter_map(url)
+                .collect(),
+        })
+    }
+}
+
+impl Backend {
+    /// Handles the `noteutil/journal` request, giving the period of a journal and the journals
+    /// around it so that editors can navigate between them. The result is null for other notes.
+    async fn journal(&self, params: TextDocumentIdentifier) -> jsonrpc::Result<Option<Journal>> {
+        Ok(self.service.journal(&params.uri))
+    }
+
+    /// Handles the `noteutil/cursorMoved` notification, which editors send to keep the preview in
+    /// sync with the cursor.
+    async fn cursor_moved(&self, params: TextDocumentPositionParams) {
+        if let Ok(path) = params.text_document.uri.to_file_path() {
+            self.service
+                .documents
+                .move_cursor(&path, params.position.line);
+        }
+    }
+}
+
 pub async fn serve(ctx: crate::Context) {
     log::info!("LSP server started.");
-    let (service, socket) = tower_lsp::LspService::new(|client| Backend {
-        client: client,
+    let (service, socket) = tower_lsp::LspService::build(|client| Backend {
+        client,
         service: Service {
             config: ctx.config.clone(),
-            documents: Arc::new(RwLock::new(HashMap::new())),
+            documents: ctx.documents.clone(),
         },
-    });
+    })
+    .custom_method("noteutil/cursorMoved", Backend::cursor_moved)
+    .custom_method("noteutil/journal", Backend::journal)
+    .finish();
     tower_lsp::Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
         .serve(service)
         .await;
@@ -165,15 +221,42 @@ mod tests {
 
     #[tokio::test]
     async fn replace_document_text() -> crate::Result<()> {
+        let uri = Url::parse("file:///tmp/note.md")?;
         let text = String::from("text");
+        let service = Service::default();
+        service.replace_document_text(&uri, text.clone()).await;
+        assert_eq!(service.document_text(&uri).await.unwrap(), "text");
+        Ok(())
+    }
+
+    #[test]
+    fn journal() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let journals = dir.path().join("journals");
+        std::fs::create_dir_all(&journals)?;
+        std::fs::write(journals.join("2023-10-20.md"), "")?;
+        std::fs::write(journals.join("2023-10-21.md"), "")?;
+
         let mut service = Service::default();
-        tokio::spawn(async {
-            service.replace_document_text("uri", text.clone()).await;
-        });
-        tokio::spawn(async {
-            assert_eq!(service.documents.read().await.get("uri").unwrap(), "text");
-        });
-        tokio::join!();
+        service.config.root_dir = dir.path().to_path_buf();
+        let url = |name: &str| Url::from_file_path(journals.join(name)).unwrap();
+
+        assert_eq!(
+            Some(Journal {
+                period: String::from("daily"),
+                start: String::from("2023-10-21"),
+                end: String::from("2023-10-21"),
+                previous: Some(url("2023-10-20.md")),
+                next: None,
+                parent: Some(url("2023-w42.md")),
+                children: Vec::new(),
+            }),
+            service.journal(&url("2023-10-21.md"))
+        );
+        assert_eq!(
+            None,
+            service.journal(&Url::from_file_path(dir.path().join("note.md")).unwrap())
+        );
         Ok(())
     }
 }
@@ -0,0 +1,408 @@
+// Conversion of TeX math to MathML, which browsers render natively. Only the common subset of
+// TeX found in notes is supported: scripts, fractions, roots, text, Greek letters, operators and
+// functions. Anything else is rendered as an error next to the rest of the formula.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::html::escape;
+
+/// Renders `tex` as a MathML formula, on its own line if `display` is set. The source is kept as
+/// an annotation so that copying the formula gives back the TeX.
+pub fn to_mathml(tex: &str, display: bool) -> String {
+    let mut parser = Parser {
+        chars: tex.chars().peekable(),
+    };
+    let row = parser.row(None);
+    let display = if display { "block" } else { "inline" };
+
+    format!(
+        r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="{}"><semantics>{}<annotation encoding="application/x-tex">{}</annotation></semantics></math>"#,
+        display,
+        row,
+        escape(tex.trim())
+    )
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    /// Parses atoms until `end` or the end of the input.
+    fn row(&mut self, end: Option<char>) -> String {
+        let mut items: Vec<String> = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek().copied() {
+                None => break,
+                Some(c) if Some(c) == end => {
+                    self.chars.next();
+                    break;
+                }
+                Some('^' | '_') => {
+                    let base = items.pop().unwrap_or_else(|| String::from("<mrow></mrow>"));
+                    items.push(self.scripts(base));
+                }
+                Some(_) => items.extend(self.atom()),
+            }
+        }
+
+        match items.len() {
+            1 => items.remove(0),
+            _ => format!("<mrow>{}</mrow>", items.concat()),
+        }
+    }
+
+    /// Attaches the subscript and superscript following `base`.
+    fn scripts(&mut self, base: String) -> String {
+        let mut sub = None;
+        let mut sup = None;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('_') if sub.is_none() => {
+                    self.chars.next();
+                    sub = Some(self.argument());
+                }
+                Some('^') if sup.is_none() => {
+                    self.chars.next();
+                    sup = Some(self.argument());
+                }
+                _ => break,
+            }
+        }
+
+        match (sub, sup) {
+            (Some(sub), Some(sup)) => format!("<msubsup>{}{}{}</msubsup>", base, sub, sup),
+            (Some(sub), None) => format!("<msub>{}{}</msub>", base, sub),
+            (None, Some(sup)) => format!("<msup>{}{}</msup>", base, sup),
+            (None, None) => base,
+        }
+    }
+
+    /// A group between braces or a single atom.
+    fn argument(&mut self) -> String {
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'{') {
+            self.chars.next();
+            return self.row(Some('}'));
+        }
+        self.atom().unwrap_or_else(|| String::from("<mrow></mrow>"))
+    }
+
+    /// The raw text of a group between braces, e.g. the argument of `\text`.
+    fn raw_argument(&mut self) -> String {
+        self.skip_whitespace();
+        if self.chars.peek() != Some(&'{') {
+            return self.chars.next().map(String::from).unwrap_or_default();
+        }
+        self.chars.next();
+
+        let mut text = String::new();
+        let mut depth = 0;
+        for c in self.chars.by_ref() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            text.push(c);
+        }
+        text
+    }
+
+    fn atom(&mut self) -> Option<String> {
+        let c = self.chars.next()?;
+        let atom = match c {
+            '{' => self.row(Some('}')),
+            '}' | '&' => return None,
+            '\\' => return self.command(),
+            '0'..='9' | '.' => {
+                let mut number = String::from(c);
+                while let Some(&c) = self.chars.peek() {
+                    if !c.is_ascii_digit() && c != '.' {
+                        break;
+                    }
+                    number.push(c);
+                    self.chars.next();
+                }
+                format!("<mn>{}</mn>", number)
+            }
+            '\'' => String::from("<mo>′</mo>"),
+            c if c.is_alphabetic() => format!("<mi>{}</mi>", c),
+            c => format!("<mo>{}</mo>", escape(&c.to_string())),
+        };
+        Some(atom)
+    }
+
+    fn command(&mut self) -> Option<String> {
+        let mut name = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(c);
+            self.chars.next();
+        }
+        if name.is_empty() {
+            name.extend(self.chars.next());
+        }
+
+        let mathml = match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                if self.chars.peek() == Some(&'[') {
+                    self.chars.next();
+                    let index = self.row(Some(']'));
+                    let radicand = self.argument();
+                    format!("<mroot>{}{}</mroot>", radicand, index)
+                } else {
+                    format!("<msqrt>{}</msqrt>", self.argument())
+                }
+            }
+            "text" | "textrm" | "mbox" => {
+                format!("<mtext>{}</mtext>", escape(&self.raw_argument()))
+            }
+            "mathrm" | "operatorname" => format!(
+                r#"<mi mathvariant="normal">{}</mi>"#,
+                escape(&self.raw_argument())
+            ),
+            "mathbf" | "mathit" | "mathbb" | "mathcal" => {
+                let variant = match name.as_str() {
+                    "mathbf" => "bold",
+                    "mathit" => "italic",
+                    "mathbb" => "double-struck",
+                    _ => "script",
+                };
+                format!(
+                    r#"<mi mathvariant="{}">{}</mi>"#,
+                    variant,
+                    escape(&self.raw_argument())
+                )
+            }
+            "left" | "right" => {
+                self.skip_whitespace();
+                let delimiter = match self.chars.next()? {
+                    // `\|` is the double bar of norms, `|` the single bar of absolute values.
+                    '\\' => match self.chars.next() {
+                        Some('|') => String::from("‖"),
+                        c => c.map(String::from).unwrap_or_default(),
+                    },
+                    '.' => return None,
+                    c => String::from(c),
+                };
+                format!(r#"<mo stretchy="true">{}</mo>"#, escape(&delimiter))
+            }
+            "begin" | "end" => {
+                // Environments are reduced to their content.
+                self.raw_argument();
+                return None;
+            }
+            "\\" => String::from(r#"<mspace linebreak="newline"/>"#),
+            "," | ":" | ";" | " " => String::from(r#"<mspace width="0.25em"/>"#),
+            "quad" => String::from(r#"<mspace width="1em"/>"#),
+            "qquad" => String::from(r#"<mspace width="2em"/>"#),
+            "!" => return None,
+            "{" | "}" | "|" | "_" | "%" | "$" | "#" | "&" => {
+                format!("<mo>{}</mo>", escape(&name))
+            }
+            name if FUNCTIONS.contains(&name) => format!("<mi>{}</mi>", name),
+            name => match SYMBOLS.iter().find(|(symbol, _, _)| *symbol == name) {
+                Some((_, tag, c)) => format!("<{}>{}</{}>", tag, c, tag),
+                None => format!("<merror><mtext>\\{}</mtext></merror>", escape(name)),
+            },
+        };
+        Some(mathml)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+static FUNCTIONS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "cos", "cosh", "cot", "deg", "det", "dim", "exp", "gcd", "inf",
+    "ker", "lg", "lim", "ln", "log", "max", "min", "Pr", "sec", "sin", "sinh", "sup", "tan",
+    "tanh",
+];
+
+/// Commands standing for a single character, with the element holding it.
+static SYMBOLS: &[(&str, &str, &str)] = &[
+    ("alpha", "mi", "α"),
+    ("beta", "mi", "β"),
+    ("gamma", "mi", "γ"),
+    ("delta", "mi", "δ"),
+    ("epsilon", "mi", "ϵ"),
+    ("varepsilon", "mi", "ε"),
+    ("zeta", "mi", "ζ"),
+    ("eta", "mi", "η"),
+    ("theta", "mi", "θ"),
+    ("vartheta", "mi", "ϑ"),
+    ("iota", "mi", "ι"),
+    ("kappa", "mi", "κ"),
+    ("lambda", "mi", "λ"),
+    ("mu", "mi", "μ"),
+    ("nu", "mi", "ν"),
+    ("xi", "mi", "ξ"),
+    ("pi", "mi", "π"),
+    ("rho", "mi", "ρ"),
+    ("sigma", "mi", "σ"),
+    ("tau", "mi", "τ"),
+    ("upsilon", "mi", "υ"),
+    ("phi", "mi", "ϕ"),
+    ("varphi", "mi", "φ"),
+    ("chi", "mi", "χ"),
+    ("psi", "mi", "ψ"),
+    ("omega", "mi", "ω"),
+    ("Gamma", "mi", "Γ"),
+    ("Delta", "mi", "Δ"),
+    ("Theta", "mi", "Θ"),
+    ("Lambda", "mi", "Λ"),
+    ("Xi", "mi", "Ξ"),
+    ("Pi", "mi", "Π"),
+    ("Sigma", "mi", "Σ"),
+    ("Phi", "mi", "Φ"),
+    ("Psi", "mi", "Ψ"),
+    ("Omega", "mi", "Ω"),
+    ("infty", "mi", "∞"),
+    ("partial", "mi", "∂"),
+    ("nabla", "mi", "∇"),
+    ("hbar", "mi", "ℏ"),
+    ("ell", "mi", "ℓ"),
+    ("emptyset", "mi", "∅"),
+    ("sum", "mo", "∑"),
+    ("prod", "mo", "∏"),
+    ("int", "mo", "∫"),
+    ("iint", "mo", "∬"),
+    ("oint", "mo", "∮"),
+    ("pm", "mo", "±"),
+    ("mp", "mo", "∓"),
+    ("times", "mo", "×"),
+    ("div", "mo", "÷"),
+    ("cdot", "mo", "⋅"),
+    ("ast", "mo", "∗"),
+    ("circ", "mo", "∘"),
+    ("leq", "mo", "≤"),
+    ("le", "mo", "≤"),
+    ("geq", "mo", "≥"),
+    ("ge", "mo", "≥"),
+    ("neq", "mo", "≠"),
+    ("ne", "mo", "≠"),
+    ("approx", "mo", "≈"),
+    ("equiv", "mo", "≡"),
+    ("sim", "mo", "∼"),
+    ("propto", "mo", "∝"),
+    ("ll", "mo", "≪"),
+    ("gg", "mo", "≫"),
+    ("in", "mo", "∈"),
+    ("notin", "mo", "∉"),
+    ("subset", "mo", "⊂"),
+    ("subseteq", "mo", "⊆"),
+    ("supset", "mo", "⊃"),
+    ("supseteq", "mo", "⊇"),
+    ("cup", "mo", "∪"),
+    ("cap", "mo", "∩"),
+    ("setminus", "mo", "∖"),
+    ("forall", "mo", "∀"),
+    ("exists", "mo", "∃"),
+    ("neg", "mo", "¬"),
+    ("land", "mo", "∧"),
+    ("wedge", "mo", "∧"),
+    ("lor", "mo", "∨"),
+    ("vee", "mo", "∨"),
+    ("to", "mo", "→"),
+    ("rightarrow", "mo", "→"),
+    ("leftarrow", "mo", "←"),
+    ("leftrightarrow", "mo", "↔"),
+    ("Rightarrow", "mo", "⇒"),
+    ("Leftarrow", "mo", "⇐"),
+    ("Leftrightarrow", "mo", "⇔"),
+    ("implies", "mo", "⇒"),
+    ("iff", "mo", "⇔"),
+    ("mapsto", "mo", "↦"),
+    ("ldots", "mo", "…"),
+    ("cdots", "mo", "⋯"),
+    ("vdots", "mo", "⋮"),
+    ("ddots", "mo", "⋱"),
+    ("langle", "mo", "⟨"),
+    ("rangle", "mo", "⟩"),
+    ("lfloor", "mo", "⌊"),
+    ("rfloor", "mo", "⌋"),
+    ("lceil", "mo", "⌈"),
+    ("rceil", "mo", "⌉"),
+    ("mid", "mo", "∣"),
+    ("perp", "mo", "⊥"),
+    ("angle", "mo", "∠"),
+    ("degree", "mo", "°"),
+];
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+
+    fn row(tex: &str) -> String {
+        Parser {
+            chars: tex.chars().peekable(),
+        }
+        .row(None)
+    }
+
+    #[test]
+    fn scripts() {
+        assert_eq!(
+            "<mrow><msup><mi>x</mi><mn>2</mn></msup><mo>+</mo><mn>1</mn></mrow>",
+            row("x^2 + 1")
+        );
+        assert_eq!(
+            "<msubsup><mi>a</mi><mrow><mi>i</mi><mo>,</mo><mi>j</mi></mrow><mn>10</mn></msubsup>",
+            row("a_{i,j}^{10}")
+        );
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            "<mfrac><mn>1</mn><msqrt><mi>π</mi></msqrt></mfrac>",
+            row(r"\frac{1}{\sqrt{\pi}}")
+        );
+        assert_eq!(
+            "<mrow><mi>sin</mi><mi>θ</mi><mo>≤</mo><mtext>one &amp; only</mtext></mrow>",
+            row(r"\sin \theta \leq \text{one & only}")
+        );
+        assert_eq!(
+            "<mrow><mo>&lt;</mo><merror><mtext>\\unknown</mtext></merror></mrow>",
+            row(r"< \unknown")
+        );
+    }
+
+    #[test]
+    fn delimiters() {
+        assert_eq!(
+            r#"<mrow><mo stretchy="true">|</mo><mi>x</mi><mo stretchy="true">|</mo></mrow>"#,
+            row(r"\left| x \right|")
+        );
+        assert_eq!(
+            r#"<mrow><mo stretchy="true">‖</mo><mi>v</mi><mo stretchy="true">‖</mo></mrow>"#,
+            row(r"\left\| v \right\|")
+        );
+        assert_eq!(
+            r#"<mrow><mo stretchy="true">{</mo><mi>x</mi></mrow>"#,
+            row(r"\left\{ x \right.")
+        );
+    }
+
+    #[test]
+    fn formula() {
+        assert_eq!(
+            r#"<math xmlns="http://www.w3.org/1998/Math/MathML" display="block"><semantics><mi>x</mi><annotation encoding="application/x-tex">x</annotation></semantics></math>"#,
+            to_mathml("x\n", true)
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -9,16 +10,116 @@ use rayon::prelude::*;
 pub struct Note {
     pub path: PathBuf,
     pub title: String,
+    pub front_matter: crate::FrontMatter,
     links: Vec<Link>,
 }
 
-#[derive(Debug, PartialEq)]
-struct Link {
-    title: Option<String>,
-    url: String,
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+pub struct Link {
+    pub title: Option<String>,
+    pub url: String,
+    /// Text of the paragraph, heading or table cell containing the link.
+    pub context: String,
+}
+
+impl Link {
+    /// The file the link points to, or `None` if it points to a website. Links absolute from the
+    /// root, e.g. `/projects/x.md`, are resolved against `root_dir`.
+    pub fn target(&self, root_dir: &Path) -> Option<PathBuf> {
+        if !Note::is_filesystem_url(&self.url) {
+            return None;
+        }
+
+        let path = match self.url.find(['#', '?']) {
+            Some(pos) => &self.url[..pos],
+            None => self.url.as_str(),
+        };
+        let path = percent_encoding::percent_decode_str(path).decode_utf8_lossy();
+        let path = Path::new(path.as_ref());
+
+        let root_dir = normalize(root_dir);
+        let path = normalize(path);
+        if path.is_absolute() && !path.starts_with(&root_dir) {
+            return Some(root_dir.join(path.strip_prefix("/").ok()?));
+        }
+
+        Some(path)
+    }
+}
+
+/// Options used to parse notes: GFM with front matter.
+pub(crate) fn parse_options() -> markdown::ParseOptions {
+    let mut options = markdown::ParseOptions::gfm();
+    options.constructs.frontmatter = true;
+    options
+}
+
+/// Options used to render notes: [`parse_options`] with math. Whatever maps the source of a note
+/// to its rendered blocks must parse it with them too.
+pub(crate) fn render_options() -> markdown::ParseOptions {
+    let mut options = parse_options();
+    options.constructs.math_flow = true;
+    options.constructs.math_text = true;
+    options
+}
+
+/// Lexically normalizes `path` by removing `.` and resolving `..` components.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
 }
 
 impl Note {
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Notes among `notes` linking to this one, along with the links.
+    pub fn backlinks<'a>(
+        &self,
+        notes: impl IntoIterator<Item = &'a Note>,
+        root_dir: &Path,
+    ) -> Vec<(&'a Note, &'a Link)> {
+        let path = normalize(&self.path);
+        notes
+            .into_iter()
+            .flat_map(|note| note.links.iter().map(move |link| (note, link)))
+            .filter(|(_, link)| link.target(root_dir).is_some_and(|target| target == path))
+            .collect()
+    }
+
+    /// Tags of the `tags` field of the front matter, given either as a list or as a string
+    /// separated by commas or spaces.
+    pub fn tags(&self) -> Vec<String> {
+        match self.front_matter.get("tags") {
+            Some(serde_json::Value::Array(tags)) => tags
+                .iter()
+                .filter_map(|tag| match tag {
+                    serde_json::Value::String(tag) => Some(tag.clone()),
+                    serde_json::Value::Null => None,
+                    tag => Some(tag.to_string()),
+                })
+                .collect(),
+            Some(serde_json::Value::String(tags)) => tags
+                .split([',', ' '])
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn link_to(&self, path: &Path) -> bool {
         for link in &self.links {
             if Path::new(link.url.as_str()) == path {
@@ -26,10 +127,15 @@ impl Note {
             }
         }
 
-        return false;
+        false
     }
 
     pub fn build(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::build_from_str(path, std::fs::read_to_string(path)?.as_str())
+    }
+
+    /// Builds the note from `content` instead of reading `path`, e.g. for unsaved buffers.
+    pub fn build_from_str(path: &Path, content: &str) -> Result<Self, Box<dyn Error>> {
         let mut note = Self {
             path: PathBuf::from(path),
             title: String::from(
@@ -38,32 +144,55 @@ impl Note {
                     .to_str()
                     .unwrap_or_default(),
             ),
+            front_matter: crate::FrontMatter::new(),
             links: Vec::new(),
         };
-        note.parse(std::fs::read_to_string(path)?.as_str())?;
+        note.parse(content)?;
 
         Ok(note)
     }
 
     fn parse(&mut self, content: &str) -> Result<(), Box<dyn Error>> {
-        let node = markdown::to_mdast(content, &markdown::ParseOptions::default())?;
-        self.parse_node(&node);
+        let node = markdown::to_mdast(content, &parse_options())?;
+        self.parse_node(&node, "");
+
+        if let Some(title) = self.front_matter.get("title").and_then(|t| t.as_str()) {
+            self.title = String::from(title);
+        }
+
         Ok(())
     }
 
-    fn parse_node(&mut self, node: &mdast::Node) {
+    fn parse_node(&mut self, node: &mdast::Node, context: &str) {
+        let block_text;
+        let context = match node {
+            mdast::Node::Paragraph(_) | mdast::Node::Heading(_) | mdast::Node::TableCell(_) => {
+                block_text = node.to_string();
+                block_text.as_str()
+            }
+            _ => context,
+        };
+
         match node {
-            mdast::Node::Link(link) => self.parse_link(link),
+            mdast::Node::Link(link) => self.parse_link(link, context),
             mdast::Node::Heading(heading) => self.parse_heading(heading),
+            mdast::Node::Yaml(yaml) => {
+                self.front_matter = crate::frontmatter::parse_yaml(&yaml.value).unwrap_or_default()
+            }
+            mdast::Node::Toml(toml) => {
+                self.front_matter = crate::frontmatter::parse_toml(&toml.value).unwrap_or_default()
+            }
             _ => {}
         }
 
         if let Some(children) = node.children() {
-            children.iter().for_each(|node| self.parse_node(&node));
+            children
+                .iter()
+                .for_each(|node| self.parse_node(node, context));
         }
     }
 
-    fn parse_link(&mut self, link: &mdast::Link) {
+    fn parse_link(&mut self, link: &mdast::Link, context: &str) {
         let title = link.children.first().and_then(|node| match &node {
             mdast::Node::Text(text) => Some(text.value.clone()),
             _ => None,
@@ -77,31 +206,33 @@ impl Note {
         };
 
         self.links.push(Link {
-            title: title,
-            url: url,
+            title,
+            url,
+            context: String::from(context),
         });
     }
 
-    // TODO: Parse front matter and other as title.
     fn parse_heading(&mut self, heading: &mdast::Heading) {
         if heading.depth == 1 {
             let title = heading.children.first().and_then(|node| match &node {
                 mdast::Node::Text(text) => Some(text.value.clone()),
                 _ => None,
             });
-            if title.is_some() {
-                self.title = title.unwrap();
+            if let Some(title) = title {
+                self.title = title;
             }
         }
     }
 
     fn is_filesystem_url(url: &str) -> bool {
-        // TODO: maybe use regex to filter any $.*://
-        !url.starts_with("https://") && !url.starts_with("http://")
+        static SCHEME: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+        let scheme =
+            SCHEME.get_or_init(|| regex::Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap());
+        !scheme.is_match(url) && !url.starts_with('#')
     }
 
     pub fn all(root_dir: &Path) -> Vec<Note> {
-        let walkdir_entries: Vec<walkdir::DirEntry> = walkdir::WalkDir::new(&root_dir)
+        let walkdir_entries: Vec<walkdir::DirEntry> = walkdir::WalkDir::new(root_dir)
             .into_iter()
             .filter_map(|e| e.ok())
             .collect();
@@ -116,10 +247,60 @@ impl Note {
     }
 }
 
+/// Replaces the content of `path` by writing a temporary file next to it and renaming it over
+/// `path`, so that readers never see a partial file. Leaves `path` alone and returns `false` when
+/// its content is no longer `expected`, `None` meaning that the file didn't exist.
+pub(crate) fn replace(
+    path: &Path,
+    content: &[u8],
+    expected: Option<&[u8]>,
+) -> std::io::Result<bool> {
+    let mut file = temp_file_for(path)?;
+    file.write_all(content)?;
+    file.as_file().sync_all()?;
+
+    if read_existing(path)?.as_deref() != expected {
+        return Ok(false);
+    }
+    file.persist(path)?;
+    Ok(true)
+}
+
+/// A hidden temporary file next to `path`, creating its directories, to be renamed over it. It
+/// has the permissions of `path`, or the usual ones of a file readable by everyone when there is
+/// none rather than the private ones of temporary files.
+pub(crate) fn temp_file_for(path: &Path) -> std::io::Result<tempfile::NamedTempFile> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    std::fs::create_dir_all(dir)?;
+    let file = tempfile::Builder::new()
+        .prefix(".")
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    match std::fs::metadata(path) {
+        Ok(metadata) => file.as_file().set_permissions(metadata.permissions())?,
+        #[cfg(unix)]
+        Err(_) => {
+            use std::os::unix::fs::PermissionsExt;
+            file.as_file()
+                .set_permissions(std::fs::Permissions::from_mode(0o644))?
+        }
+        #[cfg(not(unix))]
+        Err(_) => {}
+    }
+    Ok(file)
+}
+
+/// The content of the file at `path`, or `None` if there is none.
+pub(crate) fn read_existing(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
 #[cfg(test)]
 mod note_tests {
-    use std::io::Write;
-
     use super::*;
 
     fn temp_mdfile() -> Result<tempfile::NamedTempFile, Box<dyn Error>> {
@@ -143,6 +324,7 @@ This is a [link title](link_url).
             vec![Link {
                 title: Some(String::from("link title")),
                 url: String::from("/tmp/link_url"),
+                context: String::from("This is a link title."),
             }]
         );
 
@@ -150,4 +332,69 @@ This is a [link title](link_url).
 
         Ok(())
     }
+
+    #[test]
+    fn test_note_front_matter() -> Result<(), Box<dyn Error>> {
+        let note = Note::build_from_str(
+            Path::new("note.md"),
+            "---\ntitle: From front matter\ntags: [a, b]\n---\n# Heading\n",
+        )?;
+
+        assert_eq!(note.title, "From front matter");
+        assert_eq!(
+            note.front_matter.get("tags"),
+            Some(&serde_json::json!(["a", "b"]))
+        );
+        assert_eq!(note.tags(), vec!["a", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_note_backlinks() -> Result<(), Box<dyn Error>> {
+        let root_dir = Path::new("./notes");
+        let target = Note::build_from_str(&root_dir.join("projects/x.md"), "# X")?;
+        let notes = vec![
+            Note::build_from_str(
+                &root_dir.join("journals/today.md"),
+                "Worked on [x](../projects/x.md#goals).",
+            )?,
+            Note::build_from_str(&root_dir.join("index.md"), "- [X](/projects/x.md)")?,
+            Note::build_from_str(
+                &root_dir.join("other.md"),
+                "[web](https://example.com/projects/x.md) and [y](projects/y.md)",
+            )?,
+        ];
+
+        let backlinks: Vec<(&Path, &str)> = target
+            .backlinks(&notes, root_dir)
+            .into_iter()
+            .map(|(note, link)| (note.path.as_path(), link.context.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                (Path::new("./notes/journals/today.md"), "Worked on x."),
+                (Path::new("./notes/index.md"), "X"),
+            ],
+            backlinks
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn replaces_unchanged_files() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("notes/a.md");
+
+        assert!(replace(&path, b"first", None)?);
+        assert!(!replace(&path, b"second", None)?);
+        assert!(!replace(&path, b"second", Some(b"other"))?);
+        assert_eq!("first", std::fs::read_to_string(&path)?);
+
+        assert!(replace(&path, b"second", Some(b"first"))?);
+        assert_eq!("second", std::fs::read_to_string(&path)?);
+        assert_eq!(1, std::fs::read_dir(dir.path().join("notes"))?.count());
+        Ok(())
+    }
 }
@@ -0,0 +1,207 @@
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+};
+use tokio::sync::broadcast;
+
+use crate::documents;
+
+/// How often the file is checked for modifications on disk.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(serde::Deserialize, Debug)]
+pub struct CursorParams {
+    line: u32,
+}
+
+struct Watch {
+    /// Requested path, sent along with the events.
+    path: String,
+    filepath: PathBuf,
+    modified: Option<SystemTime>,
+    events: broadcast::Receiver<documents::Event>,
+    interval: tokio::time::Interval,
+}
+
+/// Streams server-sent events to the preview of a note: `change` when the note is modified on
+/// disk or in the editor, and `cursor` when the cursor of the editor moves.
+pub async fn serve_events(
+    State(state): State<crate::http::ServerState>,
+    Path(path): Path<String>,
+) -> Response {
+    let filepath = match crate::http::resolve_path(&state.config, &path) {
+        Some(filepath) if filepath.is_file() => filepath,
+        _ => return (StatusCode::NOT_FOUND, "Failed to found related files").into_response(),
+    };
+
+    let watch = Watch {
+        path,
+        modified: modified(&filepath),
+        filepath,
+        events: state.documents.subscribe(),
+        interval: tokio::time::interval(POLL_INTERVAL),
+    };
+
+    let stream = futures::stream::unfold(watch, |mut watch| async move {
+        let event = next_event(&mut watch).await?;
+        Some((Ok::<Event, Infallible>(event), watch))
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Moves the cursor in the preview of a note, for editors that don't talk to the LSP server.
+pub async fn move_cursor(
+    State(state): State<crate::http::ServerState>,
+    Path(path): Path<String>,
+    Query(params): Query<CursorParams>,
+) -> Response {
+    match crate::http::resolve_path(&state.config, &path) {
+        Some(filepath) if filepath.is_file() => {
+            state.documents.move_cursor(&filepath, params.line);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => (StatusCode::NOT_FOUND, "Failed to found related files").into_response(),
+    }
+}
+
+async fn next_event(watch: &mut Watch) -> Option<Event> {
+    loop {
+        tokio::select! {
+            event = watch.events.recv() => match event {
+                Ok(documents::Event::Changed(path)) if path == watch.filepath => {
+                    return Some(Event::default().event("change").data(&watch.path));
+                }
+                Ok(documents::Event::CursorMoved(path, line)) if path == watch.filepath => {
+                    return Some(Event::default().event("cursor").data(format!("{{\"line\":{}}}", line)));
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+                _ => {}
+            },
+            _ = watch.interval.tick() => {
+                let modified = modified(&watch.filepath);
+                if modified != watch.modified {
+                    watch.modified = modified;
+                    return Some(Event::default().event("change").data(&watch.path));
+                }
+            }
+        }
+    }
+}
+
+fn modified(filepath: &std::path::Path) -> Option<SystemTime> {
+    filepath.metadata().and_then(|m| m.modified()).ok()
+}
+
+/// Zero based line where each top level block of `content` starts, used to scroll the preview to
+/// the block under the cursor.
+pub(crate) fn source_lines(content: &str) -> Vec<usize> {
+    let Ok(root) = markdown::to_mdast(content, &crate::note::render_options()) else {
+        return Vec::new();
+    };
+
+    root.children()
+        .into_iter()
+        .flatten()
+        .filter(|node| {
+            !matches!(
+                node,
+                markdown::mdast::Node::Definition(_)
+                    | markdown::mdast::Node::Yaml(_)
+                    | markdown::mdast::Node::Toml(_)
+            )
+        })
+        .filter_map(|node| node.position().map(|position| position.start.line - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod preview_tests {
+    use super::*;
+
+    #[test]
+    fn lines_of_blocks() {
+        assert_eq!(
+            vec![0, 2, 4],
+            source_lines("# Title\n\nParagraph\n\n- a\n- b\n\n[a]: https://example.com\n")
+        );
+        // The front matter isn't rendered.
+        assert_eq!(
+            vec![4, 6],
+            source_lines("---\ntitle: Note\ntags: [a]\n---\n# Title\n\nParagraph\n")
+        );
+    }
+
+    /// Number of the elements at the top of `html`.
+    fn top_level_elements(html: &str) -> usize {
+        let tag = regex::Regex::new(r"<(/?)([a-zA-Z0-9]+)[^>]*?(/?)>").unwrap();
+        let (mut depth, mut count) = (0, 0);
+        for caps in tag.captures_iter(html) {
+            let void = !caps[3].is_empty() || matches!(&caps[2], "br" | "hr" | "img" | "input");
+            if !caps[1].is_empty() {
+                depth -= 1;
+            } else {
+                if depth == 0 {
+                    count += 1;
+                }
+                if !void {
+                    depth += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn lines_of_rendered_blocks() -> crate::Result<()> {
+        let content = "---\ntitle: Note\n---\n# Title\n\nSome $x$ math:\n\n$$\nx^2\n$$\n\n\
+                       ---\n\n- [ ] a\n- b\n\n| a |\n| - |\n| b |\n\n```rust\nfn main() {}\n```\n\n\
+                       > Quote\n\n[a]: https://example.com\n";
+        let html = crate::html::render(
+            content,
+            &crate::html::Links::new(
+                std::path::Path::new(""),
+                std::path::Path::new("note.md"),
+                crate::html::LinkStyle::Server { base_path: "" },
+            ),
+            &Default::default(),
+        )?;
+        assert_eq!(8, source_lines(content).len());
+        assert_eq!(source_lines(content).len(), top_level_elements(&html));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn events_of_the_file() -> crate::Result<()> {
+        let file = tempfile::NamedTempFile::new()?;
+        let other = tempfile::NamedTempFile::new()?;
+        let documents = crate::Documents::default();
+        let mut watch = Watch {
+            path: String::from("note.md"),
+            filepath: file.path().canonicalize()?,
+            modified: modified(file.path()),
+            events: documents.subscribe(),
+            interval: tokio::time::interval(POLL_INTERVAL),
+        };
+
+        documents.move_cursor(other.path(), 1);
+        documents.move_cursor(file.path(), 2);
+        let event = format!("{:?}", next_event(&mut watch).await.unwrap());
+        assert!(
+            event.contains("cursor") && event.contains("{\\\"line\\\":2}"),
+            "{}",
+            event
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use axum::{
+    extract::{Query, State},
+    response::Html,
+    response::{IntoResponse, Json, Response},
+};
+use rayon::prelude::*;
+
+use crate::html::escape;
+
+/// Maximum length of the snippets, in characters.
+const SNIPPET_LENGTH: usize = 200;
+
+/// Full-text index of the notes under a directory.
+#[derive(Default)]
+pub struct Index {
+    documents: Vec<Document>,
+}
+
+struct Document {
+    /// Path relative to the root directory, separated by slashes.
+    path: String,
+    note: crate::Note,
+    content: String,
+    /// Modification time and length of the file when it was read.
+    stamp: Option<(SystemTime, u64)>,
+}
+
+/// The index of the server, shared by the handlers and brought up to date before each use.
+#[derive(Clone, Default)]
+pub struct SharedIndex(Arc<Mutex<Index>>);
+
+#[derive(Debug, Default)]
+pub struct Search {
+    pub text: String,
+    /// Only match notes having all of these tags.
+    pub tags: Vec<String>,
+    /// Only match notes under this directory, relative to the root directory.
+    pub dir: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct Hit {
+    /// Path relative to the root directory, separated by slashes.
+    pub path: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    /// One based line of the snippet.
+    pub line: usize,
+    pub snippet: String,
+    /// Byte ranges of the snippet matching the search.
+    pub highlights: Vec<(usize, usize)>,
+    pub score: usize,
+}
+
+impl Index {
+    pub fn build(root_dir: &Path) -> Self {
+        let mut index = Self::default();
+        index.update(root_dir, |_| true);
+        index
+    }
+
+    /// Reads again the notes changed since they were indexed, adds the new ones and drops the
+    /// deleted ones. Files and directories for which `walk` is false, given their path relative to
+    /// `root_dir`, are left out without being walked.
+    pub fn update(&mut self, root_dir: &Path, walk: impl Fn(&Path) -> bool) {
+        let mut indexed: HashMap<String, Document> = std::mem::take(&mut self.documents)
+            .into_iter()
+            .map(|doc| (doc.path.clone(), doc))
+            .collect();
+
+        let mut changed = Vec::new();
+        let entries = walkdir::WalkDir::new(root_dir)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || e.path().strip_prefix(root_dir).is_ok_and(&walk))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file() && e.path().extension().is_some_and(|ext| ext == "md"));
+        for entry in entries {
+            let Ok(path) = entry.path().strip_prefix(root_dir) else {
+                continue;
+            };
+            let path: Vec<String> = path
+                .iter()
+                .map(|s| s.to_string_lossy().into_owned())
+                .collect();
+            let path = path.join("/");
+            let stamp = stamp(entry.path());
+            match indexed.remove(&path) {
+                Some(doc) if stamp.is_some() && doc.stamp == stamp => self.documents.push(doc),
+                _ => changed.push((entry, path, stamp)),
+            }
+        }
+
+        let read: Vec<Document> = changed
+            .into_par_iter()
+            .filter_map(|(entry, path, stamp)| {
+                let content = std::fs::read_to_string(entry.path()).ok()?;
+                let note = crate::Note::build_from_str(entry.path(), &content).ok()?;
+                Some(Document {
+                    path,
+                    note,
+                    content,
+                    stamp,
+                })
+            })
+            .collect();
+        self.documents.extend(read);
+        self.documents.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    /// The indexed notes, sorted by path.
+    pub fn notes(&self) -> impl Iterator<Item = &crate::Note> + Clone {
+        self.documents.iter().map(|doc| &doc.note)
+    }
+
+    pub fn into_notes(self) -> impl Iterator<Item = crate::Note> {
+        self.documents.into_iter().map(|doc| doc.note)
+    }
+
+    /// Notes containing every word of the search, best matches first.
+    pub fn search(&self, search: &Search) -> Vec<Hit> {
+        let terms: Vec<String> = search
+            .text
+            .split_whitespace()
+            .map(|term| term.to_lowercase())
+            .collect();
+        if terms.is_empty() && search.tags.is_empty() && search.dir.is_none() {
+            return Vec::new();
+        }
+
+        let pattern = terms
+            .iter()
+            .map(|term| regex::escape(term))
+            .collect::<Vec<String>>()
+            .join("|");
+        let highlight = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .ok()
+            .filter(|_| !terms.is_empty());
+
+        let mut hits: Vec<Hit> = self
+            .documents
+            .iter()
+            .filter(|doc| doc.matches_filters(search))
+            .filter_map(|doc| doc.hit(&terms, highlight.as_ref()))
+            .collect();
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.path.cmp(&b.path)));
+
+        hits
+    }
+}
+
+impl SharedIndex {
+    /// Runs `f` on the index of the notes under the root directory once updated, on a thread
+    /// where blocking is allowed. Only the directories the server may serve are walked.
+    pub async fn with<T, F>(&self, config: &crate::Config, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&Index) -> T + Send + 'static,
+    {
+        let index = self.0.clone();
+        let config = config.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut index = index.lock().unwrap_or_else(|err| err.into_inner());
+            index.update(&config.root_dir, |path| {
+                crate::http::is_walked(&config, path)
+            });
+            f(&index)
+        })
+        .await
+        .expect("Index task panicked")
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl Document {
+    fn matches_filters(&self, search: &Search) -> bool {
+        if let Some(dir) = search.dir.as_deref() {
+            let dir = dir.trim_matches('/');
+            if !dir.is_empty() && !self.path.starts_with(&format!("{}/", dir)) {
+                return false;
+            }
+        }
+
+        let tags = self.note.tags();
+        search.tags.iter().all(|tag| tags.contains(tag))
+    }
+
+    fn hit(&self, terms: &[String], highlight: Option<&regex::Regex>) -> Option<Hit> {
+        let content = self.content.to_lowercase();
+        let title = self.note.title.to_lowercase();
+
+        let mut score = 0;
+        for term in terms {
+            let count = content.matches(term.as_str()).count();
+            let title_count = title.matches(term.as_str()).count();
+            if count + title_count == 0 {
+                return None;
+            }
+            score += count + 10 * title_count;
+        }
+
+        // The line matching the most terms, or the first line with text.
+        let (line, text) = self
+            .content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .max_by_key(|(i, line)| {
+                let line = line.to_lowercase();
+                let count = terms.iter().filter(|t| line.contains(t.as_str())).count();
+                (count, std::cmp::Reverse(*i))
+            })
+            .unwrap_or((0, ""));
+
+        let snippet = excerpt(text.trim(), highlight);
+        let highlights = match highlight {
+            Some(highlight) => highlight
+                .find_iter(&snippet)
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Some(Hit {
+            path: self.path.clone(),
+            title: self.note.title.clone(),
+            tags: self.note.tags(),
+            line: line + 1,
+            snippet,
+            highlights,
+            score,
+        })
+    }
+}
+
+/// Cuts `line` down to the snippet length, keeping the first match visible.
+fn excerpt(line: &str, highlight: Option<&regex::Regex>) -> String {
+    if line.chars().count() <= SNIPPET_LENGTH {
+        return String::from(line);
+    }
+
+    let start = highlight
+        .and_then(|highlight| highlight.find(line))
+        .map(|m| {
+            line[..m.start()]
+                .chars()
+                .count()
+                .saturating_sub(SNIPPET_LENGTH / 4)
+        })
+        .unwrap_or(0);
+
+    let mut excerpt: String = line.chars().skip(start).take(SNIPPET_LENGTH).collect();
+    if start > 0 {
+        excerpt.insert(0, '…');
+    }
+    excerpt.push('…');
+    excerpt
+}
+
+/// The snippet of a hit with the matches wrapped in `<mark>`.
+pub(crate) fn snippet_html(hit: &Hit) -> String {
+    let mut html = String::new();
+    let mut last = 0;
+    for &(start, end) in &hit.highlights {
+        html.push_str(&escape(&hit.snippet[last..start]));
+        html.push_str("<mark>");
+        html.push_str(&escape(&hit.snippet[start..end]));
+        html.push_str("</mark>");
+        last = end;
+    }
+    html.push_str(&escape(&hit.snippet[last..]));
+    html
+}
+
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Params {
+    q: String,
+    /// Tags separated by commas.
+    tag: String,
+    dir: String,
+}
+
+impl Params {
+    fn search(&self) -> Search {
+        Search {
+            text: self.q.clone(),
+            tags: self
+                .tag
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+            dir: Some(self.dir.clone()).filter(|dir| !dir.is_empty()),
+        }
+    }
+}
+
+/// Hits the server is allowed to serve.
+async fn visible_hits(state: &crate::http::ServerState, params: &Params) -> Vec<Hit> {
+    let config = state.config.clone();
+    let search = params.search();
+    state
+        .index
+        .with(&state.config, move |index| {
+            index
+                .search(&search)
+                .into_iter()
+                .filter(|hit| crate::http::resolve_path(&config, &hit.path).is_some())
+                .collect()
+        })
+        .await
+}
+
+pub async fn serve_api_search(
+    State(state): State<crate::http::ServerState>,
+    Query(params): Query<Params>,
+) -> Response {
+    Json(visible_hits(&state, &params).await).into_response()
+}
+
+pub async fn serve_search(
+    State(state): State<crate::http::ServerState>,
+    Query(params): Query<Params>,
+) -> Response {
+    let base_path = state.config.http.base_path();
+    let hits = visible_hits(&state, &params).await;
+
+    let mut body = format!(
+        r#"<form class="search" action="{}/search">
+<input type="search" name="q" value="{}" placeholder="Search" autofocus>
+<input type="text" name="tag" value="{}" placeholder="Tags">
+<input type="text" name="dir" value="{}" placeholder="Directory">
+<button type="submit">Search</button>
+</form>
+"#,
+        base_path,
+        escape(&params.q),
+        escape(&params.tag),
+        escape(&params.dir),
+    );
+
+    if !params.search().text.is_empty() || !hits.is_empty() {
+        body.push_str(&format!("<p>{} results</p>\n", hits.len()));
+    }
+    body.push_str("<ul class=\"search-results\">\n");
+    for hit in &hits {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{}:{}</small><p>{}</p></li>\n",
+            escape(&crate::html::page_url(&base_path, &hit.path)),
+            escape(&hit.title),
+            escape(&hit.path),
+            hit.line,
+            snippet_html(hit),
+        ));
+    }
+    body.push_str("</ul>\n");
+
+    let mut breadcrumbs = crate::html::breadcrumbs(&base_path, "");
+    breadcrumbs.push(crate::theme::Breadcrumb {
+        name: String::from("Search"),
+        url: format!("{}/search", base_path),
+    });
+    let page = crate::theme::Page {
+        title: String::from("Search"),
+        body,
+        breadcrumbs,
+        ..Default::default()
+    };
+    Html(crate::theme::render(&state.config, &page)).into_response()
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+
+    fn index() -> crate::Result<(tempfile::TempDir, Index)> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("projects"))?;
+        std::fs::write(
+            dir.path().join("projects/rust.md"),
+            "---\ntags: [work]\n---\n# Rust\n\nLearning Rust and tokio.\n",
+        )?;
+        std::fs::write(
+            dir.path().join("journal.md"),
+            "# Journal\n\nRead about rust.\nWent running.\n",
+        )?;
+        let index = Index::build(dir.path());
+        Ok((dir, index))
+    }
+
+    fn paths(hits: &[Hit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.path.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_hits() -> crate::Result<()> {
+        let (_dir, index) = index()?;
+        let hits = index.search(&Search {
+            text: String::from("RUST"),
+            ..Default::default()
+        });
+
+        assert_eq!(vec!["projects/rust.md", "journal.md"], paths(&hits));
+        assert_eq!(4, hits[0].line);
+        assert_eq!("# Rust", hits[0].snippet);
+        assert_eq!(3, hits[1].line);
+        assert_eq!("Read about rust.", hits[1].snippet);
+        assert_eq!(vec![(11, 15)], hits[1].highlights);
+        assert_eq!("Read about <mark>rust</mark>.", snippet_html(&hits[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn requires_every_term() -> crate::Result<()> {
+        let (_dir, index) = index()?;
+        let hits = index.search(&Search {
+            text: String::from("rust running"),
+            ..Default::default()
+        });
+        assert_eq!(vec!["journal.md"], paths(&hits));
+        Ok(())
+    }
+
+    #[test]
+    fn filters() -> crate::Result<()> {
+        let (_dir, index) = index()?;
+        let by_tag = index.search(&Search {
+            tags: vec![String::from("work")],
+            ..Default::default()
+        });
+        assert_eq!(vec!["projects/rust.md"], paths(&by_tag));
+
+        let by_dir = index.search(&Search {
+            text: String::from("rust"),
+            dir: Some(String::from("projects/")),
+            ..Default::default()
+        });
+        assert_eq!(vec!["projects/rust.md"], paths(&by_dir));
+
+        assert!(index.search(&Search::default()).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn updates() -> crate::Result<()> {
+        let (dir, mut index) = index()?;
+        std::fs::write(
+            dir.path().join("journal.md"),
+            "# Journal\n\nWent swimming, a longer day.\n",
+        )?;
+        std::fs::remove_file(dir.path().join("projects/rust.md"))?;
+        std::fs::write(dir.path().join("rust.md"), "# Rust\n")?;
+        std::fs::create_dir_all(dir.path().join(".trash"))?;
+        std::fs::write(dir.path().join(".trash/rust.md"), "# Rust\n")?;
+        index.update(dir.path(), |path| path != Path::new(".trash"));
+
+        let search = |text: &str| {
+            index.search(&Search {
+                text: String::from(text),
+                ..Default::default()
+            })
+        };
+        assert_eq!(vec!["rust.md"], paths(&search("rust")));
+        assert_eq!(vec!["journal.md"], paths(&search("swimming")));
+        let titles: Vec<&str> = index.notes().map(|note| note.title.as_str()).collect();
+        assert_eq!(vec!["Journal", "Rust"], titles);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serves_changes() -> crate::Result<()> {
+        use tower::ServiceExt;
+
+        let (dir, _) = index()?;
+        let mut cx = crate::Context::default();
+        cx.config.root_dir = dir.path().to_path_buf();
+        let router = crate::http::router(cx);
+        let search = |router: axum::Router| async move {
+            let request = axum::http::Request::builder()
+                .uri("/api/search?q=swimming")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = router.oneshot(request).await.unwrap();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            serde_json::from_slice::<Vec<serde_json::Value>>(&body).unwrap()
+        };
+
+        assert!(search(router.clone()).await.is_empty());
+        std::fs::write(dir.path().join("sports.md"), "# Sports\n\nswimming\n")?;
+        let hits = search(router.clone()).await;
+        assert_eq!("sports.md", hits[0]["path"]);
+        std::fs::write(dir.path().join(".hidden.md"), "swimming\n")?;
+        assert_eq!(1, search(router).await.len());
+        Ok(())
+    }
+
+    #[test]
+    fn long_lines() {
+        let line = format!("{} needle {}", "a ".repeat(200), "b ".repeat(200));
+        let highlight = regex::Regex::new("needle").unwrap();
+        let excerpt = excerpt(&line, Some(&highlight));
+        assert!(excerpt.starts_with('…') && excerpt.contains("needle"));
+        assert_eq!(SNIPPET_LENGTH + 2, excerpt.chars().count());
+    }
+}
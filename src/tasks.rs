@@ -0,0 +1,302 @@
+// GFM task list items and sections of the journals, e.g. to carry the unfinished tasks over to
+// the next day.
+
+use markdown::mdast;
+
+/// Marker of the tasks carried over to another journal, replacing their `[ ]`.
+const MIGRATED: &str = "[>]";
+
+/// A task list item with its nested items.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Task {
+    /// Zero based line of the item.
+    pub line: usize,
+    /// Markdown of the item, unindented.
+    pub markdown: String,
+}
+
+/// The tasks of `content` that are done when `checked`, or not done otherwise. The items nested in
+/// a task are part of it rather than tasks of their own.
+pub(crate) fn tasks(content: &str, checked: bool) -> Vec<Task> {
+    let Ok(root) = markdown::to_mdast(content, &crate::note::parse_options()) else {
+        return Vec::new();
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut tasks = Vec::new();
+    collect(&root, checked, &lines, &mut tasks);
+    tasks
+}
+
+/// The tasks done in `content`, without the unfinished tasks nested in them, which are still to do
+/// rather than done with their parent.
+pub(crate) fn completed(content: &str) -> Vec<Task> {
+    let mut done = tasks(content, true);
+    for task in &mut done {
+        let open: Vec<std::ops::Range<usize>> = tasks(&task.markdown, false)
+            .iter()
+            .map(|open| open.line..open.line + open.markdown.lines().count())
+            .collect();
+        task.markdown = task
+            .markdown
+            .lines()
+            .enumerate()
+            .filter(|(line, _)| !open.iter().any(|open| open.contains(line)))
+            .map(|(_, line)| line)
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    done
+}
+
+fn collect(node: &mdast::Node, checked: bool, lines: &[&str], tasks: &mut Vec<Task>) {
+    if let mdast::Node::ListItem(item) = node {
+        if item.checked == Some(checked) {
+            if let Some(position) = &item.position {
+                tasks.push(Task {
+                    line: position.start.line - 1,
+                    markdown: unindent(lines, &position.start, &position.end),
+                });
+                return;
+            }
+        }
+    }
+
+    for child in node.children().into_iter().flatten() {
+        collect(child, checked, lines, tasks);
+    }
+}
+
+/// Lines from `start` to `end` without the indentation of the first one, nor the blank lines
+/// ending a list.
+fn unindent(
+    lines: &[&str],
+    start: &markdown::unist::Point,
+    end: &markdown::unist::Point,
+) -> String {
+    let indent = start.column - 1;
+    lines[start.line - 1..end.line.min(lines.len())]
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => line.get(indent..).unwrap_or(line),
+            _ => {
+                let spaces = line.len() - line.trim_start_matches([' ', '\t']).len();
+                &line[spaces.min(indent)..]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+/// Appends `markdown` to the end of the section under the heading `section`, e.g. `Tasks` or
+/// `## Tasks`. The section is added at the end of `content` if there is none.
+pub(crate) fn insert_into_section(content: &str, section: &str, markdown: &str) -> String {
+    let mut lines: Vec<&str> = content.lines().collect();
+
+    let Some((line, end)) = find_section(content, &lines, section) else {
+        let mut content = String::from(content.trim_end());
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        let title = section.trim_start_matches('#').trim();
+        return format!("{}## {}\n\n{}\n", content, title, markdown);
+    };
+
+    let inserted = match end == line + 1 {
+        true => format!("\n{}", markdown),
+        false => String::from(markdown),
+    };
+    let blank = end < lines.len() && !lines[end].trim().is_empty();
+    lines.insert(end, &inserted);
+    if blank {
+        lines.insert(end + 1, "");
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+/// Markdown of the section under the heading `section`, without the heading, or `None` if the
+/// section is missing or empty.
+pub(crate) fn section(content: &str, section: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (line, end) = find_section(content, &lines, section)?;
+    let markdown = lines[line + 1..end].join("\n");
+    let markdown = markdown.trim_start_matches(['\n', '\r']);
+    (!markdown.trim().is_empty()).then(|| String::from(markdown))
+}
+
+/// Zero based lines of the heading `section` and of the end of its section, without the blank
+/// lines at its end.
+fn find_section(content: &str, lines: &[&str], section: &str) -> Option<(usize, usize)> {
+    let title = section.trim_start_matches('#').trim();
+    let headings = headings(content);
+    let (index, &(line, depth, _)) = headings
+        .iter()
+        .enumerate()
+        .find(|(_, (_, _, text))| text.eq_ignore_ascii_case(title))?;
+
+    // The section ends at the next heading of the same level or above.
+    let mut end = headings[index + 1..]
+        .iter()
+        .find(|(_, other, _)| *other <= depth)
+        .map_or(lines.len(), |(line, _, _)| *line);
+    while end > line + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    Some((line, end))
+}
+
+/// Zero based lines, depths and texts of the headings of `content`.
+fn headings(content: &str) -> Vec<(usize, u8, String)> {
+    let Ok(root) = markdown::to_mdast(content, &crate::note::parse_options()) else {
+        return Vec::new();
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    root.children()
+        .into_iter()
+        .flatten()
+        .filter_map(|node| match node {
+            mdast::Node::Heading(heading) => {
+                let line = heading.position.as_ref()?.start.line - 1;
+                let text = lines[line].trim().trim_matches('#').trim();
+                Some((line, heading.depth, String::from(text)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Marks `tasks` of `content` as carried over to another journal.
+pub(crate) fn mark_migrated(content: &str, tasks: &[Task]) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    for task in tasks {
+        if let Some(line) = lines.get_mut(task.line) {
+            *line = line.replacen("[ ]", MIGRATED, 1);
+        }
+    }
+
+    let mut marked = lines.join("\n");
+    if content.ends_with('\n') {
+        marked.push('\n');
+    }
+    marked
+}
+
+#[cfg(test)]
+mod tasks_tests {
+    use super::*;
+
+    const JOURNAL: &str = "# Monday\n\n\
+                           - [x] Done\n\
+                           \x20 - [ ] Left in a done task\n\
+                           - [ ] Unfinished\n\
+                           \x20 - [x] With a done step\n\
+                           - Not a task\n\n\
+                           1. [ ] Numbered\n";
+
+    #[test]
+    fn finds_tasks() {
+        assert_eq!(
+            vec![
+                Task {
+                    line: 3,
+                    markdown: String::from("- [ ] Left in a done task"),
+                },
+                Task {
+                    line: 4,
+                    markdown: String::from("- [ ] Unfinished\n  - [x] With a done step"),
+                },
+                Task {
+                    line: 8,
+                    markdown: String::from("1. [ ] Numbered"),
+                },
+            ],
+            tasks(JOURNAL, false)
+        );
+        assert_eq!(
+            vec![2, 1],
+            tasks(JOURNAL, true)
+                .iter()
+                .map(|task| task.markdown.lines().count())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn completed_tasks() {
+        let content = "- [x] Done\n\
+                       \x20 - [ ] Left\n\
+                       \x20   - [x] Within the left one\n\
+                       \x20 - [x] Step\n\
+                       \x20 - Note\n\
+                       - [ ] Unfinished\n";
+        assert_eq!(
+            vec![Task {
+                line: 0,
+                markdown: String::from("- [x] Done\n  - [x] Step\n  - Note"),
+            }],
+            completed(content)
+        );
+    }
+
+    #[test]
+    fn inserts_into_sections() {
+        assert_eq!(
+            "# Tuesday\n\n## Tasks\n\n- [ ] Old\n- [ ] New\n\n## Notes\n",
+            insert_into_section(
+                "# Tuesday\n\n## Tasks\n\n- [ ] Old\n\n## Notes\n",
+                "Tasks",
+                "- [ ] New"
+            )
+        );
+        assert_eq!(
+            "# Tuesday\n\n## Tasks\n\n- [ ] New\n",
+            insert_into_section("# Tuesday\n\n## Tasks\n", "## Tasks", "- [ ] New")
+        );
+        assert_eq!(
+            "# Tuesday\n\n## Tasks\n\n- [ ] New\n",
+            insert_into_section("# Tuesday\n", "Tasks", "- [ ] New")
+        );
+    }
+
+    #[test]
+    fn finds_sections() {
+        let content = "# Friday\n\n## Done\n\n- [x] Review\n\n### Details\n\nLong\n\n\
+                       ## Notes\n\n## Links\n";
+        assert_eq!(
+            Some(String::from("- [x] Review\n\n### Details\n\nLong")),
+            section(content, "## Done")
+        );
+        assert_eq!(None, section(content, "Notes"));
+        assert_eq!(None, section(content, "Missing"));
+    }
+
+    #[test]
+    fn loose_lists() {
+        let content = "- [ ] A\n\n- [ ] B\n\n## Notes\n";
+        let markdown: Vec<String> = tasks(content, false)
+            .into_iter()
+            .map(|task| task.markdown)
+            .collect();
+        assert_eq!(vec!["- [ ] A", "- [ ] B"], markdown);
+        assert_eq!(
+            "## Tasks\n\n- [ ] A\n- [ ] B\n\n## Notes\n",
+            insert_into_section("## Tasks\n\n## Notes\n", "Tasks", &markdown.join("\n"))
+        );
+    }
+
+    #[test]
+    fn marks_migrated() {
+        let tasks = tasks(JOURNAL, false);
+        let marked = mark_migrated(JOURNAL, &tasks);
+        assert!(marked.contains("\n  - [>] Left in a done task\n- [>] Unfinished\n"));
+        assert!(marked.ends_with("1. [>] Numbered\n"));
+        assert!(super::tasks(&marked, false).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+// Variables of the templates rendered by `noteutil template`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+/// What a template is rendered for, besides the configuration.
+#[derive(Debug, Default)]
+pub struct Target {
+    pub date: NaiveDate,
+    pub title: Option<String>,
+    /// The note being written, if any.
+    pub path: Option<std::path::PathBuf>,
+    /// Variables given on the command line or in a file, overriding the other ones.
+    pub vars: BTreeMap<String, toml::Value>,
+}
+
+/// The context of a template: `now`, the `date`, the `title` and `path` of the note, links to the
+/// `journals` of the date, the `config`, the `env` variables and the variables of `target`.
+pub fn context(ctx: &crate::Context, target: &Target) -> crate::Result<tera::Context> {
+    let root_dir = &ctx.config.root_dir;
+    let path = target
+        .path
+        .as_ref()
+        .map(|path| crate::http::relative_path(root_dir, path));
+    // Links are relative to the note so that they work from it.
+    let dir = path
+        .as_deref()
+        .and_then(|path| Path::new(path).parent())
+        .unwrap_or(Path::new(""));
+    let title = target.title.clone().or_else(|| {
+        let stem = target.path.as_ref()?.file_stem()?;
+        Some(stem.to_string_lossy().into_owned())
+    });
+    let env: BTreeMap<String, String> = std::env::vars().collect();
+
+    let mut context = tera::Context::new();
+    context.insert("now", &chrono::Local::now().to_rfc3339());
+    context.insert("date", &target.date.to_string());
+    context.insert("title", &title);
+    context.insert("path", &path);
+    context.insert("journals", &crate::journal::links(ctx, target.date, dir));
+    context.insert("config", &ctx.config);
+    context.insert("env", &env);
+    for (key, value) in &target.vars {
+        context.insert(key, value);
+    }
+    Ok(context)
+}
+
+/// Parses the variables of a TOML file.
+pub fn read_vars(path: &Path) -> crate::Result<BTreeMap<String, toml::Value>> {
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+
+    #[test]
+    fn renders_context() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("vars.toml"),
+            "project = \"noteutil\"\nowner = \"someone\"\n",
+        )?;
+
+        let mut ctx = crate::Context::default();
+        ctx.config.root_dir = dir.path().to_path_buf();
+        let mut vars = read_vars(&dir.path().join("vars.toml"))?;
+        vars.insert(
+            String::from("owner"),
+            toml::Value::String(String::from("me")),
+        );
+        let target = Target {
+            date: NaiveDate::from_ymd_opt(2023, 10, 21).unwrap(),
+            path: Some(dir.path().join("notes/standup.md")),
+            vars,
+            ..Default::default()
+        };
+
+        let context = context(&ctx, &target)?;
+        let rendered = tera::Tera::one_off(
+            "{{ title }} {{ path }} {{ date }} [Week]({{ journals.weekly }}) \
+             {{ config.journal.week_start }} {{ project }} {{ owner }} {{ now | date(format=\"%Y\") }}",
+            &context,
+            false,
+        )?;
+        assert_eq!(
+            format!(
+                "standup notes/standup.md 2023-10-21 [Week](../journals/2023-w42.md) \
+                 Sun noteutil me {}",
+                chrono::Local::now().format("%Y")
+            ),
+            rendered
+        );
+        assert!(context.get("env").is_some());
+        assert!(context
+            .get("config")
+            .and_then(|config| config.get("http"))
+            .is_some_and(|http| http.get("auth").is_none()));
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// Layout overriding the embedded one, relative to `root_dir`. Files next to it are served under
+/// `/_theme/`.
+pub(crate) const THEME_DIR: &str = ".noteutil/theme";
+pub(crate) const LAYOUT: &str = "page.html";
+
+static DEFAULT_LAYOUT: &str = include_str!("../assets/page.html");
+
+/// Variables available to the layout, along with `base_path` and `search_url`.
+#[derive(serde::Serialize, Default, Debug)]
+pub(crate) struct Page {
+    pub title: String,
+    /// HTML of the rendered note or listing.
+    pub body: String,
+    pub front_matter: crate::FrontMatter,
+    pub breadcrumbs: Vec<Breadcrumb>,
+    pub toc: Vec<Heading>,
+    pub backlinks: Vec<PageLink>,
+    pub links: Vec<PageLink>,
+    /// The journals around the page when it's a journal.
+    pub journal: Vec<JournalLink>,
+    /// Line where each top level block of the note starts, for the live preview.
+    pub source_lines: Vec<usize>,
+    /// Server-sent events reloading the page when the note changes.
+    pub events_url: Option<String>,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub(crate) struct Breadcrumb {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub(crate) struct Heading {
+    pub depth: u8,
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub(crate) struct PageLink {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    /// Whether the link points to a missing file, or to a note left out of the export.
+    pub broken: bool,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub(crate) struct JournalLink {
+    /// `previous`, `next`, `parent` or `child`.
+    pub rel: &'static str,
+    pub title: String,
+    pub url: String,
+}
+
+/// Renders `page` through the layout of the theme in `root_dir`, falling back to the embedded
+/// layout if there is none or it is broken.
+pub(crate) fn render(config: &crate::Config, page: &Page) -> String {
+    let base_path = config.http.base_path();
+    let search_url = format!("{}/search", base_path);
+    render_with(config, page, &base_path, Some(&search_url))
+}
+
+/// Renders a page of a static site, where `base_path` is the relative path to the root of the
+/// site and there is no search.
+pub(crate) fn render_site(config: &crate::Config, page: &Page, base_path: &str) -> String {
+    render_with(config, page, base_path, None)
+}
+
+fn render_with(
+    config: &crate::Config,
+    page: &Page,
+    base_path: &str,
+    search_url: Option<&str>,
+) -> String {
+    let mut context = match tera::Context::from_serialize(page) {
+        Ok(context) => context,
+        Err(err) => {
+            log::error!("{}: Unable to build the context of the page", err);
+            tera::Context::new()
+        }
+    };
+    context.insert("base_path", base_path);
+    context.insert("search_url", &search_url);
+
+    let layout_path = config.root_dir.join(THEME_DIR).join(LAYOUT);
+    if layout_path.is_file() {
+        let layout = std::fs::read_to_string(&layout_path).map_err(tera::Error::from);
+        match layout.and_then(|layout| render_layout(&layout, &context)) {
+            Ok(html) => return html,
+            Err(err) => log::error!("{:?}: Unable to render {}", err, layout_path.display()),
+        }
+    }
+
+    render_layout(DEFAULT_LAYOUT, &context).unwrap_or_else(|err| {
+        log::error!("{:?}: Unable to render the default layout", err);
+        page.body.clone()
+    })
+}
+
+fn render_layout(layout: &str, context: &tera::Context) -> Result<String, tera::Error> {
+    let mut tera = tera::Tera::default();
+    tera.set_escape_fn(crate::html::escape);
+    tera.add_raw_template(LAYOUT, layout)?;
+    tera.render(LAYOUT, context)
+}
+
+/// Serves the stylesheets, scripts and images of the theme.
+pub async fn serve_theme_asset(
+    State(state): State<crate::http::ServerState>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let theme_dir = state.config.root_dir.join(THEME_DIR);
+    match crate::http::resolve_path_in(&theme_dir, &state.config, &path) {
+        Some(filepath) if filepath.is_file() => crate::asset::serve_file(&filepath, &headers).await,
+        _ => (StatusCode::NOT_FOUND, "Failed to found related files").into_response(),
+    }
+}
+
+#[cfg(test)]
+mod theme_tests {
+    use super::*;
+
+    fn page() -> Page {
+        Page {
+            title: String::from("<Title>"),
+            body: String::from("<p>Body</p>"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_layout() {
+        let html = render(&crate::Config::default(), &page());
+        assert!(html.contains("<title>&lt;Title&gt;</title>"));
+        assert!(html.contains("<p>Body</p>"));
+        assert!(!html.contains("preview.js"));
+    }
+
+    #[test]
+    fn custom_layout() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join(THEME_DIR))?;
+        let config = crate::Config {
+            root_dir: dir.path().to_path_buf(),
+            ..crate::Config::default()
+        };
+
+        std::fs::write(
+            dir.path().join(THEME_DIR).join(LAYOUT),
+            "<h1>{{ title }}</h1>{{ body | safe }}",
+        )?;
+        assert_eq!(
+            "<h1>&lt;Title&gt;</h1><p>Body</p>",
+            render(&config, &page())
+        );
+
+        std::fs::write(dir.path().join(THEME_DIR).join(LAYOUT), "{{ broken")?;
+        assert!(render(&config, &page()).starts_with("<!DOCTYPE html>"));
+
+        Ok(())
+    }
+}